@@ -0,0 +1,117 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use super::net::{Agent, Wire};
+
+// How many changes are kept for undoing, the oldest are forgotten first
+pub const HISTORY_LIMIT : usize = 256;
+
+// What agents and wires looked like before a change, `None` for those that
+// did not exist. Putting them back gives the change that redoes it.
+#[derive(Clone)]
+pub struct Change {
+    pub agents : HashMap<usize, Option<Agent>>,
+    pub wires : HashMap<usize, Option<Wire>>,
+    pub agent_id : usize,
+    pub wire_id : usize
+}
+
+impl Change {
+    pub fn new(agent_id : usize, wire_id : usize) -> Change {
+        Change {
+            agents: HashMap::new(),
+            wires: HashMap::new(),
+            agent_id,
+            wire_id
+        }
+    }
+
+    // Only the first value seen is kept, it is the one from before the change
+    pub fn agent(&mut self, id : usize, old : Option<&Agent>) {
+        self.agents.entry(id).or_insert_with(|| old.cloned());
+    }
+
+    pub fn wire(&mut self, id : usize, old : Option<&Wire>) {
+        self.wires.entry(id).or_insert_with(|| old.cloned());
+    }
+}
+
+#[derive(Clone)]
+pub struct History {
+    undo : VecDeque<Change>,
+    redo : Vec<Change>,
+    current : Option<Change>
+}
+
+impl fmt::Debug for History {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "History {{ undo: {}, redo: {} }}", self.undo.len(), self.redo.len())
+    }
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            current: None
+        }
+    }
+
+    // Starts recording a change, unless one is being recorded already. Only
+    // the call that started it should end it.
+    pub fn begin(&mut self, agent_id : usize, wire_id : usize) -> bool {
+        if self.current.is_some() {
+            return false;
+        }
+        self.current = Some(Change::new(agent_id, wire_id));
+        true
+    }
+
+    pub fn recording(&mut self) -> Option<&mut Change> {
+        self.current.as_mut()
+    }
+
+    // A kept change can be undone and makes the undone ones unreachable
+    pub fn end(&mut self, keep : bool) {
+        if let Some(change) = self.current.take() {
+            if keep {
+                self.redo.clear();
+                self.push_undo(change);
+            }
+        }
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Change> {
+        self.undo.pop_back()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Change> {
+        self.redo.pop()
+    }
+
+    pub fn push_undo(&mut self, change : Change) {
+        self.undo.push_back(change);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn push_redo(&mut self, change : Change) {
+        self.redo.push(change);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
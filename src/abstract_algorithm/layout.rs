@@ -0,0 +1,298 @@
+// How the interface draws a net, kept apart from the net itself so that
+// nets can be compared and hashed without coordinates. The d3 JSON of the
+// interface carries both, `Layout::to_json` and `Layout::from_json` convert.
+use std::collections::{BTreeMap, HashMap};
+
+use super::net::{Agent, AgentKind, Net, NetError, Wire};
+
+// Where an agent is drawn and what is shown on it, an empty label shows the
+// symbol of its kind
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Placement {
+    pub x : f32,
+    pub y : f32,
+    pub fixed : bool,
+    pub label : String,
+    pub title : String,
+    pub rotation : usize
+}
+
+// Placements by agent id, agents without one are drawn with the defaults
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    placements : HashMap<usize, Placement>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NodeDataArray {
+    nodes: Vec<NodeData>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NodeData {
+    id : usize,
+    x : f32,
+    y : f32,
+    fixed: bool,
+    label: String,
+	title: String,
+	rotation: usize
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FromJsonData {
+    nodes : Vec<NodeFromJsonData>,
+    links : Vec<LinkFromJsonData>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NodeFromJsonData {
+    id : usize,
+    x : f32,
+    y : f32,
+    fixed : bool,
+    kind : String,
+    label : String,
+	title : String,
+	rotation : usize,
+	p: Vec<usize>,
+    #[serde(default)]
+    level : usize,
+    #[serde(default)]
+    name : isize,
+    ports : Vec<usize>,
+    color : String,
+    width : String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LinkFromJsonDataPortAngles {
+    s : usize,
+    t : usize
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LinkFromJsonDataPortIndices {
+    s : usize,
+    t : usize
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LinkFromJsonData {
+    id : usize,
+    source : usize,
+    target : usize,
+    ports : LinkFromJsonDataPortAngles,
+    p : LinkFromJsonDataPortIndices,
+	sid: usize,
+	tid: usize,
+    force : i32
+}
+
+
+impl Layout {
+    pub fn new() -> Layout {
+        Layout::default()
+    }
+
+    pub fn placement(&self, id : usize) -> Option<&Placement> {
+        self.placements.get(&id)
+    }
+
+    pub fn place(&mut self, id : usize, placement : Placement) {
+        self.placements.insert(id, placement);
+    }
+
+    // Agents are checked before any is changed
+    pub fn update_from_json(&mut self, net : &Net, data : NodeDataArray) -> Result<(), NetError> {
+        if let Some(node) = data.nodes.iter().find(|node| !net.agents.contains_key(&node.id)) {
+            return Err(NetError::MissingAgent(node.id));
+        }
+        for node in data.nodes {
+            self.place(node.id, Placement {
+                x: node.x,
+                y: node.y,
+                fixed: node.fixed,
+                label: node.label,
+                title: node.title,
+                rotation: node.rotation
+            });
+        }
+        Ok(())
+    }
+
+    // Catches up with changes to the net: agents that are gone are forgotten
+    // and new ones are put where an agent they are wired to is, which after
+    // an interaction is next to where the pair met
+    pub fn follow(&mut self, net : &Net) {
+        self.placements.retain(|id, _| net.agents.contains_key(id));
+        if self.placements.is_empty() {
+            return;
+        }
+        let mut unplaced : Vec<_> = net.agents.keys()
+            .filter(|id| !self.placements.contains_key(id))
+            .cloned()
+            .collect();
+        while !unplaced.is_empty() {
+            let before = unplaced.len();
+            unplaced.retain(|id| {
+                let agent = net.agent(*id);
+                let neighbour = (0..agent.kind.arity())
+                    .filter_map(|port| net.wires.get(&agent[port]))
+                    .map(|wire| if wire.source == *id { wire.target } else { wire.source })
+                    .find_map(|other| self.placements.get(&other));
+                match neighbour {
+                    Some(neighbour) => {
+                        let placement = Placement {
+                            x: neighbour.x,
+                            y: neighbour.y,
+                            fixed: neighbour.fixed,
+                            ..Placement::default()
+                        };
+                        self.placements.insert(*id, placement);
+                        false
+                    },
+                    None => true
+                }
+            });
+            if unplaced.len() == before {
+                break;
+            }
+        }
+    }
+
+    // Free variables are labelled with their names from `names` unless the
+    // interface gave them a label of its own
+    pub fn to_json(&self, net : &Net, names : &HashMap<isize, &str>) -> String {
+        let mut nodes = vec![];
+        let mut links = vec![];
+        let mut idmap = HashMap::new();
+        let critical = net.find_critical_agents();
+        let default = Placement::default();
+
+        for (i, (key, agent)) in net.agents.iter().enumerate() {
+            let p = json!([agent[0], agent[1], agent[2]]);
+            let m = agent.metadata();
+            let placement = self.placements.get(key).unwrap_or(&default);
+            let (color, width) = if critical.contains(key) {
+                    ("black", "3")
+                } else {
+                    ("white", "1")
+                };
+            let label = match names.get(&agent.name) {
+                _ if !placement.label.is_empty() => placement.label.clone(),
+                Some(name) if agent.kind == AgentKind::Free => name.to_string(),
+                _ => m.1
+            };
+            idmap.insert(key, i);
+            nodes.push(json!({
+                "id": key,
+                "x": placement.x,
+                "y": placement.y,
+                "fixed": placement.fixed,
+                "kind": m.0,
+                "label": label,
+                "title": placement.title,
+                "rotation": placement.rotation,
+                "level": agent.level,
+                "name": agent.name,
+                "ports": m.2,
+                "color": color,
+                "width": width,
+                "p": p
+            }));
+        }
+
+        for (key, wire) in net.wires.iter() {
+            let source = net.agent(wire.source);
+            let target = net.agent(wire.target);
+            let (s, t) = if wire.source == wire.target {
+                source.ports_with_shared_wire()
+            } else {
+                (source.port_of(*key), target.port_of(*key))
+            };
+            let ports = json!({
+                "s": source.metadata().2[s],
+                "t": target.metadata().2[t]
+            });
+            let p = json!({
+                "s": s,
+                "t": t
+            });
+            let force = {
+                let kind_test1 = source.kind == AgentKind::Duplicator;
+                let port_test1 = source.port_of(*key) != 0;
+                let kind_test2 = target.kind == AgentKind::Duplicator;
+                let port_test2 = target.port_of(*key) != 0;
+                if (kind_test1 && port_test1) || (kind_test2 && port_test2) {
+                    0
+                } else {
+                    1
+                }
+            };
+            links.push(json!({
+                "id": key, // We cheat a little here to make from_json easier
+                "source": idmap.get(&wire.source).unwrap(),
+                "target": idmap.get(&wire.target).unwrap(),
+                "sid": wire.source,
+                "tid": wire.target,
+                "ports": ports,
+                "p": p,
+                "force": force
+            }));
+        }
+
+        let result = json!({
+            "nodes": nodes,
+            "links": links
+        });
+        result.to_string()
+    }
+
+    // The net is validated, missing ports count as unset
+    pub fn from_json(data : FromJsonData) -> Result<(Net, Layout), NetError> {
+        let mut layout = Layout::new();
+        let mut agents = BTreeMap::new();
+        let mut wires = BTreeMap::new();
+        for d in data.nodes {
+            let kind = match d.kind.as_str() {
+                "root" => AgentKind::Root,
+                "eraser" => AgentKind::Eraser,
+                "lambda" => AgentKind::Lambda,
+                "application" => AgentKind::Application,
+                "duplicator" => AgentKind::Duplicator,
+                "bracket" => AgentKind::Bracket,
+                "croissant" => AgentKind::Croissant,
+                "free" => AgentKind::Free,
+                _ => return Err(NetError::UnknownKind(d.id, d.kind))
+            };
+            // Only the root ignores the ports past its own
+            let used = if kind == AgentKind::Root { 1 } else { 3 };
+            let mut ports = [0, 0, 0];
+            for (port, wire) in ports.iter_mut().zip(d.p.iter()).take(used) {
+                *port = *wire;
+            }
+            agents.insert(d.id, Agent {
+                kind,
+                level: d.level,
+                name: d.name,
+                wires: ports
+            });
+            layout.place(d.id, Placement {
+                x: d.x,
+                y: d.y,
+                fixed: d.fixed,
+                label: d.label,
+                title: d.title,
+                rotation: d.rotation
+            });
+        }
+
+        for d in data.links {
+            wires.insert(d.id, Wire::new(d.sid, d.tid));
+        }
+
+        let net = Net::from_parts(agents, wires)?;
+        Ok((net, layout))
+    }
+}
@@ -1,3 +1,8 @@
+mod history;
+mod layout;
 mod net;
+mod notation;
 
+pub use self::layout::*;
 pub use self::net::*;
+pub use self::notation::{AgentNotation, NotationError, NotationErrorKind};
@@ -1,33 +1,102 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::{BTreeMap, HashSet, HashMap};
 use std::ops::{Index, IndexMut};
+use std::rc::Rc;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
-use typical::Tree;
+use typical::{Rules, Tree, Value};
+use limits::{Limits, Outcome};
+use super::history::{Change, History};
+use super::notation::{self, AgentNotation, NotationError};
+use utils::error_json;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
 pub enum AgentKind {
     Application,
+    Bracket,
+    Croissant,
     Duplicator,
     Eraser,
+    Free,
     Lambda,
     Root,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl AgentKind {
+    // How the kind is written in `Debug` output and in the text notation
+    pub fn header(&self) -> &'static str {
+        match self {
+            AgentKind::Root => "R",
+            AgentKind::Lambda => "L",
+            AgentKind::Application => "@",
+            AgentKind::Duplicator => "D",
+            AgentKind::Bracket => "B",
+            AgentKind::Croissant => "C",
+            AgentKind::Eraser => "e",
+            AgentKind::Free => "F"
+        }
+    }
+
+    pub fn from_header(header : char) -> Option<AgentKind> {
+        match header {
+            'R' => Some(AgentKind::Root),
+            'L' => Some(AgentKind::Lambda),
+            '@' => Some(AgentKind::Application),
+            'D' => Some(AgentKind::Duplicator),
+            'B' => Some(AgentKind::Bracket),
+            'C' => Some(AgentKind::Croissant),
+            'e' => Some(AgentKind::Eraser),
+            'F' => Some(AgentKind::Free),
+            _ => None
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+       match self {
+           | AgentKind::Duplicator
+           | AgentKind::Lambda
+           | AgentKind::Application
+           => 3,
+           | AgentKind::Bracket
+           | AgentKind::Croissant
+           => 2,
+           | AgentKind::Eraser
+           | AgentKind::Free
+           | AgentKind::Root
+           => 1
+       }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RuleKind {
     Auto,
     Cancel,
     Duplicate,
     Erase,
+    Eta,
     None
 }
 
-#[derive(Eq, Hash, Clone)]
+#[derive(Debug, Clone)]
+pub struct ReductionReport {
+    pub interactions : BTreeMap<RuleKind, usize>,
+    pub outcome : Outcome
+}
+
+impl ReductionReport {
+    pub fn total(&self) -> usize {
+        self.interactions.values().sum()
+    }
+}
+
+#[derive(Eq, Clone)]
 pub struct Wire {
-    source : usize,
-    target : usize
+    pub(super) source : usize,
+    pub(super) target : usize
 }
 
+// Wires have no direction, the same ends either way round are the same wire
 impl PartialEq for Wire {
     fn eq(&self, other : &Wire) -> bool {
         let (s1, t1) = (self.source, self.target);
@@ -36,6 +105,13 @@ impl PartialEq for Wire {
     }
 }
 
+impl Hash for Wire {
+    fn hash<H : Hasher>(&self, state : &mut H) {
+        self.source.min(self.target).hash(state);
+        self.source.max(self.target).hash(state);
+    }
+}
+
 impl fmt::Debug for Wire {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}-{}", self.target, self.source)
@@ -56,22 +132,190 @@ impl Wire {
     }
 
     pub fn swap(&mut self) {
-        let temp = self.source;
-        self.source = self.target;
-        self.target = temp;
+        std::mem::swap(&mut self.source, &mut self.target);
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadbackError {
+    MissingRoot,
+    UnexpectedPort(usize, usize),
+    UnbalancedBracket(usize),
+    UnbalancedDuplicator(usize),
+    UnboundedPath(usize),
+    UnboundVariable(usize)
+}
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadbackError::MissingRoot
+                => write!(f, "The net has no root agent."),
+            ReadbackError::UnexpectedPort(id, port)
+                => write!(f, "Agent {} cannot be read back through port {}.", id, port),
+            ReadbackError::UnbalancedBracket(id)
+                => write!(f, "Bracket {} was entered from its principal port without a matching path.", id),
+            ReadbackError::UnbalancedDuplicator(id)
+                => write!(f, "Duplicator {} was entered from its principal port without a matching path.", id),
+            ReadbackError::UnboundedPath(id)
+                => write!(f, "The path through duplicator {} does not terminate.", id),
+            ReadbackError::UnboundVariable(id)
+                => write!(f, "Lambda {} is not bound on the current path.", id)
+        }
+    }
+}
+
+impl ReadbackError {
+    pub fn id(&self) -> Option<usize> {
+        match self {
+            ReadbackError::MissingRoot => None,
+            | ReadbackError::UnexpectedPort(id, _)
+            | ReadbackError::UnbalancedBracket(id)
+            | ReadbackError::UnbalancedDuplicator(id)
+            | ReadbackError::UnboundedPath(id)
+            | ReadbackError::UnboundVariable(id)
+            => Some(*id)
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReadbackError::MissingRoot => "MissingRoot",
+            ReadbackError::UnexpectedPort(_, _) => "UnexpectedPort",
+            ReadbackError::UnbalancedBracket(_) => "UnbalancedBracket",
+            ReadbackError::UnbalancedDuplicator(_) => "UnbalancedDuplicator",
+            ReadbackError::UnboundedPath(_) => "UnboundedPath",
+            ReadbackError::UnboundVariable(_) => "UnboundVariable"
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        error_json(self.kind(), &self.to_string(), json!({ "id": self.id() }))
+    }
+}
+
+// A broken invariant of a net, see `Net::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    MissingWire(usize, usize, usize),  // Agent, port and the wire it names
+    UnusedPort(usize, usize, usize),   // Agent, port past its arity and the wire it names
+    DetachedPort(usize, usize, usize), // Agent, port and a wire that does not end at the agent
+    MissingAgent(usize, usize),        // Wire and the agent at one of its ends
+    PortCount(usize, usize, usize),    // Wire, agent at one of its ends and how many ports of the agent name it
+    RootCount(usize)
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::MissingWire(id, port, wire)
+                => write!(f, "Port {} of agent {} names wire {}, which does not exist.", port, id, wire),
+            Violation::UnusedPort(id, port, wire)
+                => write!(f, "Agent {} has no port {}, but it names wire {}.", id, port, wire),
+            Violation::DetachedPort(id, port, wire)
+                => write!(f, "Port {} of agent {} names wire {}, which does not end at the agent.", port, id, wire),
+            Violation::MissingAgent(wire, id)
+                => write!(f, "Wire {} ends at agent {}, which does not exist.", wire, id),
+            Violation::PortCount(wire, id, count)
+                => write!(f, "Wire {} ends at agent {}, which names it on {} ports.", wire, id, count),
+            Violation::RootCount(count)
+                => write!(f, "The net has {} root agents instead of one.", count)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetError {
+    MissingAgent(usize),
+    MissingWire(usize),
+    UnknownKind(usize, String), // Agent and the kind it was given
+    Invalid(Vec<Violation>)
+}
+
+impl NetError {
+    pub fn id(&self) -> Option<usize> {
+        match self {
+            NetError::MissingAgent(id) | NetError::MissingWire(id) | NetError::UnknownKind(id, _) => Some(*id),
+            NetError::Invalid(_) => None
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetError::MissingAgent(_) => "MissingAgent",
+            NetError::MissingWire(_) => "MissingWire",
+            NetError::UnknownKind(_, _) => "UnknownKind",
+            NetError::Invalid(_) => "Invalid"
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        error_json(self.kind(), &self.to_string(), json!({ "id": self.id() }))
+    }
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetError::MissingAgent(id)
+                => write!(f, "There is no agent {}.", id),
+            NetError::MissingWire(id)
+                => write!(f, "There is no wire {}.", id),
+            NetError::UnknownKind(id, kind)
+                => write!(f, "Agent {} has the unknown kind {:?}.", id, kind),
+            NetError::Invalid(violations) => {
+                write!(f, "The net is inconsistent.")?;
+                for violation in violations {
+                    write!(f, " {}", violation)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// A step recorded while reading back a path, see `Net::to_tree`
+#[derive(Debug, Clone, PartialEq)]
+enum Trace {
+    Port(usize),
+    Pair(Vec<Trace>, Vec<Trace>)
+}
+
+// What is left to do while reading back, see `Net::to_tree`. Reading a port
+// leaves one term behind, an abstraction and an application are built from
+// the terms their sides left.
+enum ReadStep {
+    Read(usize, usize, Rc<Vec<Vec<Trace>>>),
+    Abs(isize, isize),
+    App
+}
+
+impl Trace {
+    // Whether every level of `context` starts with the corresponding level of
+    // `prefix`, levels missing from either are empty
+    fn extends(context : &[Vec<Trace>], prefix : &[Vec<Trace>]) -> bool {
+        prefix.iter().enumerate().all(|(level, traces)| {
+            Trace::starts_with(context.get(level).map_or(&[], |t| t.as_slice()), traces)
+        })
+    }
+
+    fn starts_with(traces : &[Trace], prefix : &[Trace]) -> bool {
+        prefix.len() <= traces.len() && prefix.iter().zip(traces.iter()).all(|pair| match pair {
+            (Trace::Port(x), Trace::Port(y)) => x == y,
+            (Trace::Pair(lower1, upper1), Trace::Pair(lower2, upper2))
+                => Trace::starts_with(lower2, lower1) && Trace::starts_with(upper2, upper1),
+            _ => false
+        })
     }
 }
 
-#[derive(Clone)]
+// Where an agent is drawn is kept apart, see `Layout`
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Agent {
-    kind : AgentKind,
-    label : String,
-	title : String,
-	rotation : usize,
-    x : f32,
-    y : f32,
-    fixed : bool,
-    wires : [usize; 3]
+    pub(super) kind : AgentKind,
+    pub(super) level : usize,
+    pub(super) name : isize,
+    pub(super) wires : [usize; 3]
 }
 
 impl Agent {
@@ -79,12 +323,8 @@ impl Agent {
     fn new(kind : AgentKind, wires : Vec<usize>) -> Agent {
         let mut result = Agent {
             kind,
-            label: String::new(),
-			title: String::new(),
-			rotation: 0,
-            x: 0.,
-            y: 0.,
-            fixed: false,
+            level: 0,
+            name: 0,
             wires: [0, 0, 0]
         };
         result.update(wires);
@@ -92,15 +332,7 @@ impl Agent {
     }
 
     fn len(&self) -> usize {
-       match self.kind {
-           | AgentKind::Duplicator
-           | AgentKind::Lambda
-           | AgentKind::Application
-           => 3,
-           | AgentKind::Eraser
-           | AgentKind::Root
-           => 1
-       }
+        self.kind.arity()
     }
 
     fn update(&mut self, wires : Vec<usize>) {
@@ -109,7 +341,7 @@ impl Agent {
         }
     }
 
-    fn port_of(&self, wire : usize) -> usize {
+    pub(super) fn port_of(&self, wire : usize) -> usize {
         let mut result = 0;
         for i in 0..self.len() {
             if self.wires[i] == wire {
@@ -119,7 +351,7 @@ impl Agent {
         result
     }
 
-    fn ports_with_shared_wire(&self) -> (usize, usize) {
+    pub(super) fn ports_with_shared_wire(&self) -> (usize, usize) {
         if self.wires[0] == self.wires[1] {
             (0, 1)
         } else if self.wires[1] == self.wires[2] {
@@ -131,6 +363,20 @@ impl Agent {
         }
     }
 
+    // A copy of this agent after passing through `other`. Brackets and
+    // croissants shift the level of agents above their own level.
+    fn copy_past(&self, other : &Agent) -> Agent {
+        let mut copy = self.clone();
+        if other.level < self.level {
+            match other.kind {
+                AgentKind::Bracket => copy.level += 1,
+                AgentKind::Croissant => copy.level -= 1,
+                _ => {}
+            }
+        }
+        copy
+    }
+
     pub fn metadata(&self) -> (String, String, Vec<usize>) {
         match self.kind {
             AgentKind::Root
@@ -142,36 +388,29 @@ impl Agent {
             AgentKind::Application
                 => ("application".to_string(), "@".to_string(), vec![135, 270, 45]),
             AgentKind::Duplicator
-                => ("duplicator".to_string(), "△".to_string(), vec![270, 45, 135])
+                => ("duplicator".to_string(), "△".to_string(), vec![270, 45, 135]),
+            AgentKind::Bracket
+                => ("bracket".to_string(), "[".to_string(), vec![270, 90]),
+            AgentKind::Croissant
+                => ("croissant".to_string(), "⌓".to_string(), vec![270, 90]),
+            AgentKind::Free
+                => ("free".to_string(), "•".to_string(), vec![270])
         }
     }
-
-    fn agent_port_orientation(&self, port : usize) -> usize {
-        let v = match self.kind {
-            AgentKind::Root => vec![90],
-            AgentKind::Eraser => vec![270],
-            AgentKind::Lambda => vec![270, 45, 135],
-            AgentKind::Application => vec![135, 270, 45],
-            AgentKind::Duplicator => vec![270, 45, 135]
-        };
-        v[port]
-    }
 }
 
 impl fmt::Debug for Agent {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let header = match self.kind {
-            AgentKind::Root => "R",
-            AgentKind::Lambda => "L",
-            AgentKind::Application => "@",
-            AgentKind::Duplicator => "D",
-            AgentKind::Eraser => "e"
-        };
+        let header = self.kind.header();
 
         match self.kind {
             AgentKind::Root | AgentKind::Eraser
                 => write!(f, "{}[{}]", header, self[0]),
-            _ => write!(f, "{}[{},{},{}]", header, self[0], self[1], self[2]),
+            AgentKind::Free
+                => write!(f, "{}{}[{}]", header, self.name, self[0]),
+            AgentKind::Bracket | AgentKind::Croissant
+                => write!(f, "{}{}[{},{}]", header, self.level, self[0], self[1]),
+            _ => write!(f, "{}{}[{},{},{}]", header, self.level, self[0], self[1], self[2]),
         }
     }
 }
@@ -190,74 +429,38 @@ impl IndexMut<usize> for Agent {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct NodeDataArray {
-    nodes: Vec<NodeData>
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct NodeData {
-    id : usize,
-    x : f32,
-    y : f32,
-    fixed: bool,
-    label: String,
-	title: String,
-	rotation: usize
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct FromJsonData {
-    nodes : Vec<NodeFromJsonData>,
-    links : Vec<LinkFromJsonData>
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct NodeFromJsonData {
-    id : usize,
-    x : f32,
-    y : f32,
-    fixed : bool,
-    kind : String,
-    label : String,
-	title : String,
-	rotation : usize,
-	p: Vec<usize>,
-    ports : Vec<usize>,
-    color : String,
-    width : String,
+#[derive(Debug, Clone)]
+pub struct Net {
+    agent_id : usize,
+    wire_id : usize,
+    // Ordered by id so that everything iterating them, the JSON and the
+    // reduction order included, is the same from run to run
+    pub(super) agents : BTreeMap<usize, Agent>,
+    pub(super) wires : BTreeMap<usize, Wire>,
+    history : History
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct LinkFromJsonDataPortAngles {
-    s : usize,
-    t : usize
+impl Default for Net {
+    fn default() -> Net {
+        Net::new()
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct LinkFromJsonDataPortIndices {
-    s : usize,
-    t : usize
+// Nets are compared by their agents and wires, how they came to be and the
+// ids the next ones get do not matter
+impl PartialEq for Net {
+    fn eq(&self, other : &Net) -> bool {
+        self.agents == other.agents && self.wires == other.wires
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct LinkFromJsonData {
-    id : usize,
-    source : usize,
-    target : usize,
-    ports : LinkFromJsonDataPortAngles,
-    p : LinkFromJsonDataPortIndices,
-	sid: usize,
-	tid: usize,
-    force : i32
-}
+impl Eq for Net {}
 
-#[derive(Debug)]
-pub struct Net {
-    agent_id : usize,
-    wire_id : usize,
-    agents : HashMap<usize, Agent>,
-    wires : HashMap<usize, Wire>
+impl Hash for Net {
+    fn hash<H : Hasher>(&self, state : &mut H) {
+        self.agents.hash(state);
+        self.wires.hash(state);
+    }
 }
 
 impl Net {
@@ -265,276 +468,540 @@ impl Net {
         Net {
             agent_id: 1,
             wire_id: 1,
-            agents: HashMap::new(),
-            wires: HashMap::new()
-        }
-    }
-
-    pub fn update_from_json(&mut self, data : NodeDataArray) {
-        for node in data.nodes {
-            let agent = self.mut_agent(node.id);
-            agent.x = node.x;
-            agent.y = node.y;
-            agent.fixed = node.fixed;
-            agent.label = node.label;
-			agent.title = node.title;
-			agent.rotation = node.rotation;
-        }
-    }
-
-    pub fn from_json(data : FromJsonData) -> Net {
-        let mut max_agent_id = 0;
-        let mut max_link_id = 0;
-        let mut agent_map = HashMap::new();
-        let mut link_map = HashMap::new();
-        for d in data.nodes {
-			let kind = match d.kind.as_str() {
-			    "root" => AgentKind::Root,
-			    "eraser" => AgentKind::Eraser,
-			    "lambda" => AgentKind::Lambda,
-			    "application" => AgentKind::Application,
-			    "duplicator" => AgentKind::Duplicator,
-			    _ => AgentKind::Eraser
-		    };
-
-			let agent = if kind == AgentKind::Root {
-				Agent {
-					kind,
-					label: d.label,
-					title: d.title,
-					rotation: d.rotation,
-					x: d.x,
-					y: d.y,
-					fixed: d.fixed,
-					wires: [d.p[0],0,0],
-				}
-			} else {
-				Agent {
-					kind,
-					label: d.label,
-					title: d.title,
-					rotation: d.rotation,
-					x: d.x,
-					y: d.y,
-					fixed: d.fixed,
-					wires: [d.p[0], d.p[1], d.p[2]],
-				}
-			};
-			max_agent_id = std::cmp::max(max_agent_id, d.id);
-			agent_map.insert(d.id, agent);
-        }
-
-        for d in data.links {
-            let wire = Wire {
-                source: d.sid,
-                target: d.tid
-            };
-            max_link_id = std::cmp::max(max_link_id, d.id);
-            link_map.insert(d.id, wire);
+            agents: BTreeMap::new(),
+            wires: BTreeMap::new(),
+            history: History::new()
         }
+    }
 
-        Net {
-            agent_id: max_agent_id + 1,
-            wire_id: max_link_id + 1,
-            agents: agent_map,
-            wires: link_map
+    // A net made of the given agents and wires, as long as they are consistent
+    pub(super) fn from_parts(agents : BTreeMap<usize, Agent>, wires : BTreeMap<usize, Wire>) -> Result<Net, NetError> {
+        let net = Net {
+            agent_id: agents.keys().max().map_or(1, |id| id + 1),
+            wire_id: wires.keys().max().map_or(1, |id| id + 1),
+            agents,
+            wires,
+            history: History::new()
+        };
+        let violations = net.validate();
+        if violations.is_empty() {
+            Ok(net)
+        } else {
+            Err(NetError::Invalid(violations))
         }
     }
 
-    pub fn to_json(&self) -> String {
-        let mut nodes = vec![];
-        let mut links = vec![];
-        let mut idmap = HashMap::new();
+    // A Graphviz graph of the net. Agents are records with their principal
+    // port, marked ●, above the auxiliary ones, edges are labelled with their
+    // wire ids and active pairs are drawn bold. Free variables are labelled
+    // with their names from `names`.
+    pub fn to_dot(&self, names : &HashMap<isize, &str>) -> String {
         let critical = self.find_critical_agents();
-
-        let mut i = 0;
-        for (key, agent) in self.agents.iter() {
-			let p = json!([agent[0],agent[1],agent[2]]);
-            let m = agent.metadata();
-            let (color, width) = if critical.contains(&key) { 
-                    ("black", "3")
-                } else { 
-                    ("white", "1")
-                };
-            idmap.insert(key, i);
-            nodes.push(json!({
-                "id": key,
-                "x": agent.x,
-                "y": agent.y,
-                "fixed": agent.fixed,
-                "kind": m.0,
-                "label": if agent.label.is_empty() { m.1 }
-                    else { agent.label.clone() },
-				"title": agent.title,
-				"rotation": agent.rotation,
-                "ports": m.2,
-                "color": color,
-                "width": width,
-				"p": p
-            }));
-            i += 1;
-        }
-
-        for (key, wire) in self.wires.iter() {
-            let source = self.agent(wire.source);
-            let target = self.agent(wire.target);
-            let (sangle, tangle, s, t) = if wire.source == wire.target {
-                let (x, y) = source.ports_with_shared_wire();
-                (source.agent_port_orientation(x),
-                source.agent_port_orientation(y),
-                x, y)
-            } else {
-                let (x, y) = (source.port_of(*key), target.port_of(*key));
-                (source.agent_port_orientation(x),
-                target.agent_port_orientation(y),
-                x, y)
+        let ids : Vec<_> = self.agents.keys().cloned().collect();
+        let wire_ids : Vec<_> = self.wires.keys().cloned().collect();
+
+        let mut result = String::new();
+        result.push_str("graph net {\n");
+        result.push_str("    node [shape=record, fontname=\"monospace\"];\n");
+        result.push_str("    edge [fontname=\"monospace\", fontsize=10];\n");
+        for id in ids {
+            let agent = self.agent(id);
+            let (kind, symbol, _) = agent.metadata();
+            let name = match names.get(&agent.name) {
+                Some(name) if agent.kind == AgentKind::Free => format!("{} {}", symbol, name),
+                _ => symbol
             };
-            let ports = json!({
-                "s": sangle,
-                "t": tangle
-            });
-            let p = json!({
-                "s": s,
-                "t": t
-            });
-            let force = {
-                let kind_test1 = source.kind == AgentKind::Duplicator;
-                let port_test1 = source.port_of(*key) != 0;
-                let kind_test2 = target.kind == AgentKind::Duplicator;
-                let port_test2 = target.port_of(*key) != 0;
-                if (kind_test1 && port_test1) || (kind_test2 && port_test2) {
-                    0
-                } else {
-                    1
-                }
+            let center = format!("{} {}\\n#{}", Net::escape_record(&name), agent.level, id);
+            let label = match agent.len() {
+                1 => format!("{{<p0> ●|{}}}", center),
+                2 => format!("{{<p0> ●|{}|<p1> 1}}", center),
+                _ => format!("{{<p0> ●|{}|{{<p1> 1|<p2> 2}}}}", center)
             };
-            links.push(json!({
-                "id": key, // We cheat a little here to make from_json easier
-                "source": idmap.get(&wire.source).unwrap(),
-                "target": idmap.get(&wire.target).unwrap(),
-				"sid": wire.source,
-				"tid": wire.target,
-                "ports": ports,
-                "p": p,
-                "force": force
-            }));
+            let style = if critical.contains(&id) { ", penwidth=3" } else { "" };
+            result.push_str(&format!("    {} [label=\"{}\", tooltip=\"{}\"{}];\n", id, label, kind, style));
         }
+        for key in wire_ids {
+            let wire = &self.wires[&key];
+            let (s, t) = if wire.source == wire.target {
+                self.agent(wire.source).ports_with_shared_wire()
+            } else {
+                (self.agent(wire.source).port_of(key), self.agent(wire.target).port_of(key))
+            };
+            let active = wire.source != wire.target
+                && Net::valid_pair(self.agent(wire.source), self.agent(wire.target));
+            let style = if active { ", penwidth=3, color=red" } else { "" };
+            result.push_str(&format!("    {}:p{} -- {}:p{} [label=\"{}\"{}];\n",
+                wire.source, s, wire.target, t, key, style));
+        }
+        result.push_str("}\n");
+        result
+    }
 
-        let result = json!({
-            "nodes": nodes,
-            "links": links
-        });
-        result.to_string()
+    // Characters with a meaning in record labels or quoted strings
+    fn escape_record(text : &str) -> String {
+        let mut result = String::new();
+        for c in text.chars() {
+            if "{}|<>\"\\".contains(c) {
+                result.push('\\');
+            }
+            result.push(c);
+        }
+        result
     }
 
-    pub fn from_tree(tree : &Tree) -> Net {
+    // Reads a net in the notation of the `notation` module. Names are given
+    // ids from 1 in the order they first appear, the map returned takes them
+    // back to their names like `Parser::names_map`.
+    pub fn from_text(text : &str) -> Result<(Net, HashMap<isize, String>), NotationError> {
+        let agents = notation::parse(text)?;
         let mut net = Net::new();
-        let mut map = HashMap::new();
-        let root_id = net.add_agent(Agent::new(AgentKind::Root, vec![0]));
-        let root_wire = net.add_wire(Wire::new(root_id, 0));
-        net.mut_agent(root_id)[0] = root_wire;
-        let remaining = net.from_tree_helper(tree, root_wire, &mut map);
-        net.mut_wire(root_wire).target = remaining;
-        net.fix_dangling_lambdas();
-        net
+        let mut ids : HashMap<String, isize> = HashMap::new();
+        let mut ports : HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+        for agent in agents.iter() {
+            let id = net.add_agent(Agent::new(agent.kind, vec![0; agent.kind.arity()]));
+            net.mut_agent(id).level = agent.level;
+            if let Some(name) = &agent.name {
+                let next = ids.len() as isize + 1;
+                let name_id = *ids.entry(name.clone()).or_insert(next);
+                net.mut_agent(id).name = name_id;
+            }
+            for (port, wire) in agent.wires.iter().enumerate() {
+                ports.entry(wire.as_str()).or_default().push((id, port));
+            }
+        }
+        // Wires are numbered in the order their names first appear
+        let mut order : Vec<_> = ports.into_iter().collect();
+        order.sort_by_key(|(_, ends)| ends[0]);
+        for (_, ends) in order {
+            net.link(ends[0], ends[1]);
+        }
+        debug_assert!(net.validate().is_empty());
+        let names = ids.into_iter().map(|(name, id)| (id, name)).collect();
+        Ok((net, names))
     }
 
-    fn fix_dangling_lambdas(&mut self) {
+    // Writes the net in the notation of the `notation` module, agents in the
+    // order of their ids and wires named in the order they first appear.
+    // Names missing from `names` are written as their ids.
+    pub fn to_text(&self, names : &HashMap<isize, &str>) -> String {
+        let ids : Vec<_> = self.agents.keys().cloned().collect();
+        let mut wire_names = HashMap::new();
         let mut agents = vec![];
-        for (key, val) in self.agents.iter() {
-            if val[2] == 0 && val.kind == AgentKind::Lambda {
-                agents.push(*key);
-            }
+        for id in ids {
+            let agent = self.agent(id);
+            let name = if agent.kind != AgentKind::Lambda && agent.kind != AgentKind::Free {
+                None
+            } else if let Some(name) = names.get(&agent.name) {
+                Some(name.to_string())
+            } else if agent.name != 0 {
+                Some(agent.name.to_string())
+            } else {
+                None
+            };
+            let wires = (0..agent.len()).map(|port| {
+                let next = wire_names.len();
+                wire_names.entry(agent[port]).or_insert_with(|| notation::wire_name(next)).clone()
+            }).collect();
+            agents.push(AgentNotation { kind: agent.kind, level: agent.level, name, wires });
         }
+        notation::print(&agents)
+    }
 
-        for id in agents {
-            let eid = self.add_agent(Agent::new(AgentKind::Eraser, vec![0]));
-            let wire = self.add_wire(Wire::new(id, eid));
-            self.mut_agent(eid)[0] = wire;
-            self.mut_agent(id)[2] = wire;
+    pub fn from_tree(tree : &Tree) -> Net {
+        let mut net = Net::new();
+        let root_id = net.add_agent(Agent::new(AgentKind::Root, vec![0]));
+        let free = net.translate_tree(tree, (root_id, 0), 0);
+        if !free.is_empty() {
+            panic!("Bound variables without a binder, the tree is not canonical.");
         }
+        net
     }
 
-    fn from_tree_helper(&mut self, tree : &Tree, dangling : usize, name_map : &mut HashMap<usize, usize>) -> usize {
+    // Translates `tree` at the given level, attaching its root to `parent`,
+    // following Lamping's translation. Arguments of an application live one
+    // level deeper than the application, every variable occurrence sits behind
+    // a croissant of its own level and every free variable leaving an argument
+    // passes through a bracket of the application's level. The result maps the
+    // free variables of `tree` to the port that still has to be connected to
+    // their binder, occurrences of the same variable are contracted by a
+    // duplicator at the level of the application joining them. Variables
+    // without a binder become inert free agents, one per occurrence, that are
+    // copied and erased like any other value.
+    fn translate_tree(&mut self,
+        tree : &Tree,
+        parent : (usize, usize),
+        level : usize) -> BTreeMap<isize, (usize, usize)>
+    {
         match tree {
-            Tree::Var(id_isize, _) => {
-                let id = *id_isize as usize;
-                let lambda_id = *name_map.get(&id).expect("Free variables are not supported.");
-
-                if self.agent(lambda_id)[2] == 0 {
-                    self.mut_agent(lambda_id)[2] = dangling;
-                    self.mut_wire(dangling).swap();
-                    lambda_id
+            Tree::Var(name, 0) => {
+                let free_id = self.add_agent(Agent::new(AgentKind::Free, vec![0]));
+                self.mut_agent(free_id).name = *name;
+                self.mut_agent(free_id).level = level;
+                self.link(parent, (free_id, 0));
+                BTreeMap::new()
+            },
+            Tree::Var(_, bound_id) => {
+                let croissant_id = self.add_agent(Agent::new(AgentKind::Croissant, vec![0, 0]));
+                self.mut_agent(croissant_id).level = level;
+                self.link(parent, (croissant_id, 1));
+
+                let mut free = BTreeMap::new();
+                free.insert(*bound_id, (croissant_id, 0));
+                free
+            },
+            Tree::Abs(name, bound_id, body) => {
+                let lambda_id = self.add_agent(Agent::new(AgentKind::Lambda, vec![0, 0, 0]));
+                self.mut_agent(lambda_id).name = *name;
+                self.mut_agent(lambda_id).level = level;
+                self.link(parent, (lambda_id, 0));
+
+                let mut free = self.translate_tree(body, (lambda_id, 1), level);
+                if let Some(occurrence) = free.remove(bound_id) {
+                    self.link((lambda_id, 2), occurrence);
                 } else {
-                    let previous_wire = self.agent(lambda_id)[2];
-                    let new_wire = self.add_wire(Wire::new(lambda_id, 0));
-                    let dup_id = self.add_agent(Agent::new(
-                        AgentKind::Duplicator,
-                        vec![new_wire, previous_wire, dangling])
-                    );
-                    self.mut_wire(new_wire).fill(dup_id);
-                    self.mut_agent(lambda_id)[2] = new_wire;
-                    if self.wire(previous_wire).target == lambda_id {
-                        self.mut_wire(previous_wire).target = dup_id;
+                    let eraser_id = self.add_agent(Agent::new(AgentKind::Eraser, vec![0]));
+                    self.link((lambda_id, 2), (eraser_id, 0));
+                }
+                free
+            },
+            Tree::App(left, right) => {
+                let application_id = self.add_agent(Agent::new(AgentKind::Application, vec![0, 0, 0]));
+                self.mut_agent(application_id).level = level;
+                self.link(parent, (application_id, 1));
+
+                let mut free = self.translate_tree(left, (application_id, 0), level);
+                let right_free = self.translate_tree(right, (application_id, 2), level + 1);
+                for (bound_id, occurrence) in right_free {
+                    let bracket_id = self.add_agent(Agent::new(AgentKind::Bracket, vec![0, 0]));
+                    self.mut_agent(bracket_id).level = level;
+                    self.link((bracket_id, 1), occurrence);
+
+                    if let Some(left_occurrence) = free.remove(&bound_id) {
+                        let dup_id = self.add_agent(Agent::new(AgentKind::Duplicator, vec![0, 0, 0]));
+                        self.mut_agent(dup_id).level = level;
+                        self.link((dup_id, 1), left_occurrence);
+                        self.link((dup_id, 2), (bracket_id, 0));
+                        free.insert(bound_id, (dup_id, 0));
                     } else {
-                        self.mut_wire(previous_wire).source = dup_id;
+                        free.insert(bound_id, (bracket_id, 0));
                     }
-                    self.mut_wire(dangling).swap();
-                    dup_id
                 }
-            },
-            Tree::Abs(id_isize, _, body) => {
-                let id = *id_isize as usize;
+                free
+            }
+        }
+    }
 
-                let lambda_id = self.add_agent(Agent::new(AgentKind::Lambda, vec![0, 0, 0]));
-                let body_wire = self.add_wire(Wire::new(lambda_id, 0));
-                self.mut_agent(lambda_id).update(vec![dangling, body_wire, 0]);
+    fn root(&self) -> Result<usize, ReadbackError> {
+        self.agents.iter()
+            .find(|(_, agent)| agent.kind == AgentKind::Root)
+            .map(|(key, _)| *key)
+            .ok_or(ReadbackError::MissingRoot)
+    }
+
+    // Reads the term hanging below the root, entering agents from above.
+    // Duplicators, brackets and croissants are crossed with `cross`. A variable
+    // is bound by the innermost abstraction on the path through the same lambda
+    // whose context the context at the variable extends. The paths are walked
+    // with a stack of their own so deep terms do not exhaust the thread's, and
+    // the two sides of an application share a context until one changes it.
+    pub fn to_tree(&self) -> Result<Tree, ReadbackError> {
+        let (id, port) = self.follow(self.root()?, 0);
+        let mut steps = vec![ReadStep::Read(id, port, Rc::new(vec![]))];
+        let mut trees = vec![];
+        let mut binders = vec![];
+        let mut bound_id = 0;
+        while let Some(step) = steps.pop() {
+            match step {
+                ReadStep::Read(id, port, mut context) => {
+                    let agent = self.agent(id);
+                    match (agent.kind, port) {
+                        (AgentKind::Lambda, 0) => {
+                            bound_id += 1;
+                            let (body_id, body_port) = self.follow(id, 1);
+                            binders.push((id, bound_id, context.clone()));
+                            steps.push(ReadStep::Abs(agent.name, bound_id));
+                            steps.push(ReadStep::Read(body_id, body_port, context));
+                        },
+                        (AgentKind::Lambda, 2) => {
+                            let bound = binders.iter().rev()
+                                .find(|(lambda_id, _, prefix)| *lambda_id == id && Trace::extends(&context, prefix))
+                                .map(|(_, bound, _)| *bound)
+                                .ok_or(ReadbackError::UnboundVariable(id))?;
+                            trees.push(Tree::Var(agent.name, bound));
+                        },
+                        (AgentKind::Free, 0) => {
+                            trees.push(Tree::Var(agent.name, 0));
+                        },
+                        (AgentKind::Application, 1) => {
+                            let (left_id, left_port) = self.follow(id, 0);
+                            let (right_id, right_port) = self.follow(id, 2);
+                            steps.push(ReadStep::App);
+                            steps.push(ReadStep::Read(right_id, right_port, context.clone()));
+                            steps.push(ReadStep::Read(left_id, left_port, context));
+                        },
+                        _ => {
+                            let exit = self.cross(id, port, Rc::make_mut(&mut context))?;
+                            let (next_id, next_port) = self.follow(id, exit);
+                            steps.push(ReadStep::Read(next_id, next_port, context));
+                        }
+                    }
+                },
+                ReadStep::Abs(name, bound) => {
+                    binders.pop();
+                    let body = trees.pop().expect("The body was read.");
+                    trees.push(Tree::Abs(name, bound, Box::new(body)));
+                },
+                ReadStep::App => {
+                    let right = trees.pop().expect("The argument was read.");
+                    let left = trees.pop().expect("The function was read.");
+                    trees.push(Tree::App(Box::new(left), Box::new(right)));
+                }
+            }
+        }
+        Ok(trees.pop().expect("The root term was read."))
+    }
+
+    // The number, boolean or list the read-back term encodes, if any, with the
+    // type guessed like `Tree::decode` does
+    pub fn to_value(&self) -> Result<Option<Value>, ReadbackError> {
+        Ok(self.to_tree()?.decode())
+    }
 
-                name_map.insert(id, lambda_id);
-                let body_id = self.from_tree_helper(body, body_wire, name_map);
-                self.mut_wire(body_wire).fill(body_id);
-                lambda_id
+    // Walks the paths the read-back takes from the ports in `pending`, leaving
+    // a duplicator entered through its principal port through both auxiliary
+    // ones instead of tracing which, and returns the first active agent met
+    // with the port that led to it. Pairs in garbage or in arguments that were
+    // erased are never met. Agents are entered through each port once, so a
+    // walk is linear in the size of the net and can go on after an
+    // interaction from the port it stopped at.
+    fn needed_pair(&self, pending : &mut Vec<(usize, usize)>, visited : &mut HashSet<(usize, usize)>)
+        -> Option<(usize, (usize, usize))>
+    {
+        while let Some((from, exit)) = pending.pop() {
+            if !self.agents.contains_key(&from) { continue; }
+            let (id, port) = self.follow(from, exit);
+            if !visited.insert((id, port)) { continue; }
+            if self.is_active(id) {
+                return Some((id, (from, exit)));
+            }
+            let exits : &[usize] = match (self.agent(id).kind, port) {
+                (AgentKind::Lambda, 0) => &[1],
+                (AgentKind::Application, 1) => &[2, 0],
+                (AgentKind::Duplicator, 0) => &[2, 1],
+                (AgentKind::Duplicator, _) => &[0],
+                (AgentKind::Croissant, 0) | (AgentKind::Bracket, 0) => &[1],
+                (AgentKind::Croissant, _) | (AgentKind::Bracket, _) => &[0],
+                _ => &[]
+            };
+            pending.extend(exits.iter().map(|exit| (id, *exit)));
+        }
+        None
+    }
+
+    // Crosses a duplicator, bracket or croissant entered through `port` and
+    // returns the port to leave through. The context holds one stack per
+    // level. Entering a duplicator through an auxiliary port pushes the port
+    // taken onto its level, and entering one through its principal port leaves
+    // through the port popped from its level. Crossing a croissant towards its
+    // principal port opens a fresh level at its own, and a bracket folds its
+    // level and the next one into one, crossing either in the other direction
+    // undoes this.
+    fn cross(&self, id : usize, port : usize, context : &mut Vec<Vec<Trace>>) -> Result<usize, ReadbackError> {
+        let agent = self.agent(id);
+        let level = agent.level;
+        if context.len() <= level + 1 {
+            context.resize(level + 2, vec![]);
+        }
+
+        let exit = match (agent.kind, port) {
+            (AgentKind::Duplicator, 0) => {
+                match context[level].pop() {
+                    Some(Trace::Port(exit)) => exit,
+                    _ => return Err(ReadbackError::UnbalancedDuplicator(id))
+                }
             },
-            Tree::App(left, right) => {
-                let application_id = self.add_agent(Agent::new(AgentKind::Application, vec![0, 0, 0]));
-                let left_wire = self.add_wire(Wire::new(application_id, 0));
-                let right_wire = self.add_wire(Wire::new(application_id, 0));
-                self.mut_agent(application_id).update(vec![left_wire, dangling, right_wire]);
+            (AgentKind::Duplicator, _) => {
+                // A finite term never needs more pending duplicators than exist
+                let pending : usize = context.iter().map(|traces| traces.len()).sum();
+                if pending >= self.agents.len() {
+                    return Err(ReadbackError::UnboundedPath(id));
+                }
+                context[level].push(Trace::Port(port));
+                0
+            },
+            (AgentKind::Croissant, 0) => {
+                context.remove(level);
+                1
+            },
+            (AgentKind::Croissant, _) => {
+                context.insert(level, vec![]);
+                0
+            },
+            (AgentKind::Bracket, 0) => {
+                let traces = std::mem::take(&mut context[level]);
+                match traces.as_slice() {
+                    [] => context.insert(level + 1, vec![]),
+                    [Trace::Pair(lower, upper)] => {
+                        context[level] = lower.clone();
+                        context.insert(level + 1, upper.clone());
+                    },
+                    _ => return Err(ReadbackError::UnbalancedBracket(id))
+                }
+                1
+            },
+            (AgentKind::Bracket, _) => {
+                let upper = context.remove(level + 1);
+                let lower = std::mem::take(&mut context[level]);
+                if !lower.is_empty() || !upper.is_empty() {
+                    context[level] = vec![Trace::Pair(lower, upper)];
+                }
+                0
+            },
+            _ => return Err(ReadbackError::UnexpectedPort(id, port))
+        };
+        Ok(exit)
+    }
 
-                let left_id = self.from_tree_helper(left, left_wire, name_map);
-                self.mut_wire(left_wire).fill(left_id);
+    // Every port of an agent names a wire that ends at the agent, every wire
+    // ends at agents that name it on one port each, or two when both of its
+    // ends are the same agent, and there is a single root. The other methods
+    // assume all of this.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = vec![];
+        let agent_ids : Vec<usize> = self.agents.keys().cloned().collect();
+        let wire_ids : Vec<usize> = self.wires.keys().cloned().collect();
+
+        for id in agent_ids.iter() {
+            let agent = self.agent(*id);
+            for port in 0..agent.wires.len() {
+                let wire_id = agent[port];
+                if port >= agent.len() {
+                    if wire_id != 0 {
+                        violations.push(Violation::UnusedPort(*id, port, wire_id));
+                    }
+                    continue;
+                }
+                match self.wires.get(&wire_id) {
+                    None => violations.push(Violation::MissingWire(*id, port, wire_id)),
+                    Some(wire) if wire.source != *id && wire.target != *id
+                        => violations.push(Violation::DetachedPort(*id, port, wire_id)),
+                    _ => ()
+                }
+            }
+        }
 
-                let right_id = self.from_tree_helper(right, right_wire, name_map);
-                self.mut_wire(right_wire).fill(right_id);
-                application_id
+        for wire_id in wire_ids.iter() {
+            let wire = self.wire(*wire_id);
+            let (ends, expected) = if wire.source == wire.target {
+                (vec![wire.source], 2)
+            } else {
+                (vec![wire.source, wire.target], 1)
+            };
+            for id in ends {
+                match self.agents.get(&id) {
+                    None => violations.push(Violation::MissingAgent(*wire_id, id)),
+                    Some(agent) => {
+                        let count = (0..agent.len()).filter(|port| agent[*port] == *wire_id).count();
+                        if count != expected {
+                            violations.push(Violation::PortCount(*wire_id, id, count));
+                        }
+                    }
+                }
             }
         }
+
+        let roots = self.agents.values().filter(|agent| agent.kind == AgentKind::Root).count();
+        if roots != 1 {
+            violations.push(Violation::RootCount(roots));
+        }
+        violations
+    }
+
+    // Finds the agent and port on the other end of the wire attached to `port`
+    fn follow(&self, id : usize, port : usize) -> (usize, usize) {
+        let agent = self.agent(id);
+        let wire_id = agent[port];
+        let wire = self.wire(wire_id);
+        if wire.source == wire.target {
+            let other = (0..agent.len())
+                .find(|&i| i != port && agent[i] == wire_id)
+                .unwrap_or(port);
+            (id, other)
+        } else {
+            let other_id = if wire.source == id { wire.target } else { wire.source };
+            (other_id, self.agent(other_id).port_of(wire_id))
+        }
     }
 
+    // Every change to agents and wires goes through the functions below, so
+    // the history sees what they were before
     fn add_agent(&mut self, agent : Agent) -> usize {
-        self.agents.insert(self.agent_id, agent);
+        let id = self.agent_id;
+        self.insert_agent(id, agent);
         self.agent_id += 1;
-        self.agent_id - 1
+        id
+    }
+
+    fn insert_agent(&mut self, id : usize, agent : Agent) {
+        if let Some(change) = self.history.recording() {
+            change.agent(id, self.agents.get(&id));
+        }
+        self.agents.insert(id, agent);
+    }
+
+    fn remove_agent(&mut self, id : usize) -> Agent {
+        if let Some(change) = self.history.recording() {
+            change.agent(id, self.agents.get(&id));
+        }
+        self.agents.remove(&id).unwrap()
     }
 
     fn mut_agent(&mut self, id : usize) -> &mut Agent {
+        if let Some(change) = self.history.recording() {
+            change.agent(id, self.agents.get(&id));
+        }
         self.agents.get_mut(&id).unwrap()
     }
 
-    fn agent(&self, id : usize) -> &Agent {
+    pub(super) fn agent(&self, id : usize) -> &Agent {
         self.agents.get(&id).unwrap()
     }
 
     fn add_wire(&mut self, wire : Wire) -> usize {
-        self.wires.insert(self.wire_id, wire);
+        let id = self.wire_id;
+        self.insert_wire(id, wire);
         self.wire_id += 1;
-        self.wire_id - 1
+        id
+    }
+
+    fn insert_wire(&mut self, id : usize, wire : Wire) {
+        if let Some(change) = self.history.recording() {
+            change.wire(id, self.wires.get(&id));
+        }
+        self.wires.insert(id, wire);
+    }
+
+    fn remove_wire(&mut self, id : usize) -> Option<Wire> {
+        if let Some(change) = self.history.recording() {
+            change.wire(id, self.wires.get(&id));
+        }
+        self.wires.remove(&id)
     }
 
     fn mut_wire(&mut self, id : usize) -> &mut Wire {
+        if let Some(change) = self.history.recording() {
+            change.wire(id, self.wires.get(&id));
+        }
         self.wires.get_mut(&id).unwrap()
     }
 
+    fn link(&mut self, (source, source_port) : (usize, usize), (target, target_port) : (usize, usize)) -> usize {
+        let wire = self.add_wire(Wire::new(source, target));
+        self.mut_agent(source)[source_port] = wire;
+        self.mut_agent(target)[target_port] = wire;
+        wire
+    }
+
     fn wire(&self, id : usize) -> &Wire {
         self.wires.get(&id).unwrap()
     }
@@ -543,39 +1010,59 @@ impl Net {
         port : usize,
         wire_id : usize,
         old_id : usize,
-        new_id : usize)
+        new_id : usize) -> Result<(), NetError>
     {
-        let wire = self.wires.get_mut(&wire_id).unwrap();
-        let new = self.agents.get_mut(&new_id).unwrap();
+        if !self.wires.contains_key(&wire_id) {
+            return Err(NetError::MissingWire(wire_id));
+        }
+        if !self.agents.contains_key(&new_id) {
+            return Err(NetError::MissingAgent(new_id));
+        }
+        self.reattach(port, wire_id, old_id, new_id);
+        Ok(())
+    }
+
+    // Moves the end of the wire at `old_id` to the given port of `new_id`,
+    // both of which must exist
+    fn reattach(&mut self, port : usize, wire_id : usize, old_id : usize, new_id : usize) {
+        let wire = self.mut_wire(wire_id);
         if wire.source == old_id {
             wire.source = new_id;
-            new[port] = wire_id;
         } else {
             wire.target = new_id;
-            new[port] = wire_id;
         }
+        self.mut_agent(new_id)[port] = wire_id;
     }
 
     pub fn connect(&mut self,
         dangling1_id : usize,
         wire1_id : usize,
         dangling2_id : usize,
-        wire2_id : usize)
+        wire2_id : usize) -> Result<(), NetError>
     {
-        let wire1 = self.wires.remove(&wire1_id).unwrap();
-        let wire2 = self.wires.remove(&wire2_id).unwrap();
+        // Everything is looked up before the net is changed
+        let wire1 = self.wires.get(&wire1_id).ok_or(NetError::MissingWire(wire1_id))?;
+        let wire2 = self.wires.get(&wire2_id).ok_or(NetError::MissingWire(wire2_id))?;
         let agent1_id = if wire1.source == dangling1_id
             { wire1.target }
             else { wire1.source };
         let agent2_id = if wire2.source == dangling2_id
             { wire2.target }
             else { wire2.source };
+        for id in [agent1_id, agent2_id].iter() {
+            if !self.agents.contains_key(id) {
+                return Err(NetError::MissingAgent(*id));
+            }
+        }
+        self.remove_wire(wire1_id);
+        self.remove_wire(wire2_id);
         let port1 = self.agent(agent1_id).port_of(wire1_id);
         let port2 = self.agent(agent2_id).port_of(wire2_id);
         let wire = Wire::new(agent1_id, agent2_id);
         let id = self.add_wire(wire);
         self.mut_agent(agent1_id)[port1] = id;
         self.mut_agent(agent2_id)[port2] = id;
+        Ok(())
     }
 
     fn valid_pair(agent : &Agent, partner : &Agent) -> bool {
@@ -591,36 +1078,411 @@ impl Net {
             | (_, AgentKind::Root)
             | (AgentKind::Lambda, AgentKind::Lambda)
             | (AgentKind::Application, AgentKind::Application)
+            | (AgentKind::Application, AgentKind::Free)
+            | (AgentKind::Free, AgentKind::Free)
+            | (AgentKind::Free, AgentKind::Lambda)
                  => false,
-            _ => true && port_test
+            _ => port_test
         }
     }
 
-    fn find_critical_agents(&self) -> HashSet<usize> {
-        let mut set = HashSet::new();
-        for (_, agent) in self.agents.iter() {
-            let incident = agent[0];
-            let wire = self.wire(incident);
+    // Whether the agent meets another one on its principal port
+    fn is_active(&self, id : usize) -> bool {
+        let (other, port) = self.follow(id, 0);
+        other != id && port == 0 && Net::valid_pair(self.agent(id), self.agent(other))
+    }
+
+    // The ports outside of the pair of the given active agent that its wires
+    // lead to
+    fn neighbours(&self, id : usize) -> Vec<(usize, usize)> {
+        let partner = self.follow(id, 0).0;
+        let mut result = vec![];
+        for agent in [id, partner].iter() {
+            for port in 0..self.agent(*agent).len() {
+                let end = self.follow(*agent, port);
+                if end.0 != id && end.0 != partner {
+                    result.push(end);
+                }
+            }
+        }
+        result
+    }
+
+    fn active_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for (_, wire) in self.wires.iter() {
+            if wire.source == wire.target { continue; }
             let test = Net::valid_pair(
                 self.agent(wire.source),
                 self.agent(wire.target));
             if test {
-                set.insert(wire.source);
-                set.insert(wire.target);
+                pairs.push((wire.source, wire.target));
             }
         }
+        pairs
+    }
+
+    pub(super) fn find_critical_agents(&self) -> HashSet<usize> {
+        let mut set = HashSet::new();
+        for (source, target) in self.active_pairs() {
+            set.insert(source);
+            set.insert(target);
+        }
         set
     }
 
-    pub fn reduction_step(&mut self, id : usize, rule : RuleKind) {
-        // Find the two agents and wire that are part of the rule
-        let (incident, wid) = {
-            let agent = self.agents.get(&id).unwrap();
-            (self.wires.remove(&agent[0]).unwrap(), agent[0])
+    // Fires the pairs the normal form needs, see `needed_pair`, until there
+    // are none left. A net without a root fires the first active pair by id
+    // instead. A normalization is undone as a whole.
+    pub fn normalize(&mut self, limit : &Limits) -> Result<ReductionReport, NetError> {
+        let outermost = self.history.begin(self.agent_id, self.wire_id);
+        let result = self.normalize_helper(limit);
+        if outermost {
+            self.history.end(matches!(result, Ok(ref report) if report.total() > 0));
+        }
+        result
+    }
+
+    fn normalize_helper(&mut self, limit : &Limits) -> Result<ReductionReport, NetError> {
+        let start = limit.start();
+        let mut interactions = BTreeMap::new();
+        let mut steps = 0;
+        let root = self.root().ok();
+        let mut pending = vec![];
+        let mut visited = HashSet::new();
+        let outcome = loop {
+            let needed = match root {
+                Some(root) => {
+                    // Pairs that became active behind the walk are found by
+                    // a fresh one from the root
+                    let mut needed = self.needed_pair(&mut pending, &mut visited);
+                    if needed.is_none() {
+                        pending = vec![(root, 0)];
+                        visited.clear();
+                        needed = self.needed_pair(&mut pending, &mut visited);
+                    }
+                    needed.map(|(id, resume)| {
+                        pending.push(resume);
+                        id
+                    })
+                },
+                None => self.active_pairs().first().map(|(id, _)| *id)
+            };
+            let id = match needed {
+                Some(id) => id,
+                None => break Outcome::Normal
+            };
+            if let Some(outcome) = limit.check(steps, &start).or_else(|| limit.check_size(|| self.agents.len())) {
+                break outcome;
+            }
+
+            let touched = self.neighbours(id);
+            // A pair that needs a rule from the user can not make progress
+            let kind = self.reduction_step(id, RuleKind::Auto)?;
+            if kind == RuleKind::None {
+                break Outcome::Stuck;
+            }
+            // Agents the walk went through whose other ports were rewired may
+            // lead somewhere new
+            for &(other, _) in touched.iter() {
+                if !self.agents.contains_key(&other) { continue; }
+                for port in 0..self.agent(other).len() {
+                    if !touched.contains(&(other, port)) && visited.remove(&(other, port)) {
+                        pending.push(self.follow(other, port));
+                    }
+                }
+            }
+            *interactions.entry(kind).or_insert(0) += 1;
+            steps += 1;
         };
+        Ok(ReductionReport { interactions, outcome })
+    }
+
+    // Like `normalize`, then with `Rules::BetaEta` the eta redexes of the
+    // normal form are contracted. Contracting one never creates an active pair.
+    // Redexes whose variable is still shared with erased garbage are not
+    // recognized, see `eta_redex`.
+    pub fn normalize_with(&mut self, rules : Rules, limit : &Limits) -> Result<ReductionReport, NetError> {
+        let outermost = self.history.begin(self.agent_id, self.wire_id);
+        let result = self.normalize_with_helper(rules, limit);
+        if outermost {
+            self.history.end(matches!(result, Ok(ref report) if report.total() > 0));
+        }
+        result
+    }
+
+    fn normalize_with_helper(&mut self, rules : Rules, limit : &Limits) -> Result<ReductionReport, NetError> {
+        let start = limit.start();
+        let mut report = self.normalize(limit)?;
+        if rules == Rules::Beta || report.outcome != Outcome::Normal {
+            return Ok(report);
+        }
+        loop {
+            let lambdas : Vec<usize> = self.agents.iter()
+                .filter(|(_, agent)| agent.kind == AgentKind::Lambda)
+                .map(|(id, _)| *id)
+                .collect();
+
+            let mut fired = false;
+            for id in lambdas {
+                if let Some(outcome) = limit.check(report.total(), &start) {
+                    report.outcome = outcome;
+                    return Ok(report);
+                }
+                if !self.agents.contains_key(&id) { continue; }
+                if self.reduction_step(id, RuleKind::Eta)? == RuleKind::Eta {
+                    *report.interactions.entry(RuleKind::Eta).or_insert(0) += 1;
+                    fired = true;
+                }
+            }
+            if !fired {
+                return Ok(report);
+            }
+        }
+    }
+
+    // Takes back the last interaction or normalization, returns whether there
+    // was one. Agents come back where they were before it.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop_undo() {
+            Some(change) => {
+                let inverse = self.apply(change);
+                self.history.push_redo(inverse);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.history.pop_redo() {
+            Some(change) => {
+                let inverse = self.apply(change);
+                self.history.push_undo(inverse);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    // Puts back the recorded agents and wires, returning the change that
+    // puts back the current ones
+    fn apply(&mut self, change : Change) -> Change {
+        let mut inverse = Change::new(self.agent_id, self.wire_id);
+        for (id, agent) in change.agents {
+            let current = match agent {
+                Some(agent) => self.agents.insert(id, agent),
+                None => self.agents.remove(&id)
+            };
+            inverse.agents.insert(id, current);
+        }
+        for (id, wire) in change.wires {
+            let current = match wire {
+                Some(wire) => self.wires.insert(id, wire),
+                None => self.wires.remove(&id)
+            };
+            inverse.wires.insert(id, current);
+        }
+        self.agent_id = change.agent_id;
+        self.wire_id = change.wire_id;
+        inverse
+    }
+
+    // Finds the application of an eta redex at the lambda `id`, a lambda whose
+    // body applies something to its variable and nothing else. On the way to
+    // the argument the variable may cross brackets and croissants, as long as
+    // each bracket is closed by a croissant one level up, like the pair the
+    // translation puts there. Together these leave the context alone. They
+    // are returned along with the application.
+    fn eta_redex(&self, id : usize) -> Option<(usize, Vec<usize>)> {
+        let lambda = self.agents.get(&id)?;
+        if lambda.kind != AgentKind::Lambda { return None; }
+        let (app_id, port) = self.follow(id, 1);
+        let application = self.agent(app_id);
+        if application.kind != AgentKind::Application || port != 1 || application.level != lambda.level {
+            return None;
+        }
+        if self.follow(id, 0).0 == app_id {
+            return None;
+        }
+
+        let mut controls = vec![];
+        let mut open = vec![];
+        let mut current = self.follow(id, 2);
+        while current != (app_id, 2) {
+            let agent = self.agent(current.0);
+            if current.1 != 0 { return None; }
+            match agent.kind {
+                AgentKind::Bracket => open.push(agent.level),
+                AgentKind::Croissant if open.last().map(|level| level + 1) == Some(agent.level) => { open.pop(); },
+                _ => return None
+            }
+            controls.push(current.0);
+            current = self.follow(current.0, 1);
+        }
+        if open.is_empty() {
+            Some((app_id, controls))
+        } else {
+            None
+        }
+    }
+
+    // Puts the function of the application where the lambda was
+    fn eta_contract(&mut self, id : usize, app_id : usize, controls : Vec<usize>) {
+        let outside = self.follow(id, 0);
+        let function = self.follow(app_id, 0);
+        for old_id in [id, app_id].iter().chain(controls.iter()) {
+            let old = self.remove_agent(*old_id);
+            for port in 0..old.len() {
+                self.remove_wire(old[port]);
+            }
+        }
+        self.link(outside, function);
+    }
+
+    // Connects auxiliary port i of `agent` to auxiliary port i of `partner`.
+    // Wires running between auxiliary ports of the pair are followed until
+    // both ends are outside of the pair, closed loops simply disappear.
+    fn annihilate(&mut self, (aid, agent) : (usize, &Agent), (pid, partner) : (usize, &Agent)) {
+        let pair = [(aid, agent), (pid, partner)];
+        let mut visited = HashSet::new();
+        for side in 0..2 {
+            for port in 1..pair[side].1.len() {
+                if visited.contains(&(side, port)) { continue; }
+                let start = match self.across(&pair, (side, port)) {
+                    Err(start) => start,
+                    Ok(_) => continue
+                };
+                visited.insert((side, port));
+
+                let mut current = (1 - side, port);
+                let end = loop {
+                    visited.insert(current);
+                    match self.across(&pair, current) {
+                        Err(end) => break end,
+                        Ok((next_side, next_port)) => {
+                            visited.insert((next_side, next_port));
+                            current = (1 - next_side, next_port);
+                        }
+                    }
+                };
+                self.link(start, end);
+            }
+        }
+
+        for (_, old) in pair.iter() {
+            for port in 1..old.len() {
+                self.remove_wire(old[port]);
+            }
+        }
+    }
+
+    // Finds what is on the other end of an auxiliary port of an interacting
+    // pair, either another port of the pair or a port outside of it
+    fn across(&self,
+        pair : &[(usize, &Agent); 2],
+        (side, port) : (usize, usize)) -> Result<(usize, usize), (usize, usize)>
+    {
+        let (id, agent) = pair[side];
+        let wire_id = agent[port];
+        let wire = self.wire(wire_id);
+        if wire.source == wire.target {
+            let other = (1..agent.len())
+                .find(|&i| i != port && agent[i] == wire_id)
+                .unwrap_or(port);
+            Ok((side, other))
+        } else {
+            let other_id = if wire.source == id { wire.target } else { wire.source };
+            let (partner_id, partner) = pair[1 - side];
+            if other_id == partner_id {
+                Ok((1 - side, partner.port_of(wire_id)))
+            } else {
+                Err((other_id, self.agent(other_id).port_of(wire_id)))
+            }
+        }
+    }
+
+    // Lets `agent` and `partner` pass through each other. Every auxiliary port
+    // of one receives a copy of the other, and the copies are wired together.
+    // Erasing is the case where one side has no auxiliary ports at all.
+    fn commute(&mut self, (aid, agent) : (usize, &Agent), (pid, partner) : (usize, &Agent)) {
+        let agent_copies : Vec<usize> = (1..partner.len())
+            .map(|_| self.add_agent(agent.copy_past(partner)))
+            .collect();
+        let partner_copies : Vec<usize> = (1..agent.len())
+            .map(|_| self.add_agent(partner.copy_past(agent)))
+            .collect();
+
+        for (i, partner_copy) in partner_copies.iter().enumerate() {
+            for (j, agent_copy) in agent_copies.iter().enumerate() {
+                self.link((*agent_copy, i + 1), (*partner_copy, j + 1));
+            }
+        }
+        for (j, agent_copy) in agent_copies.iter().enumerate() {
+            self.reattach(0, partner[j + 1], pid, *agent_copy);
+        }
+        for (i, partner_copy) in partner_copies.iter().enumerate() {
+            self.reattach(0, agent[i + 1], aid, *partner_copy);
+        }
+    }
+
+    // Applies a rule, which can be undone unless it is part of a larger
+    // change like a normalization
+    pub fn reduction_step(&mut self, id : usize, rule : RuleKind) -> Result<RuleKind, NetError> {
+        let outermost = self.history.begin(self.agent_id, self.wire_id);
+        let result = self.interact(id, rule);
+        if outermost {
+            self.history.end(matches!(result, Ok(kind) if kind != RuleKind::None));
+        }
+        #[cfg(debug_assertions)]
+        {
+            let violations = self.validate();
+            assert!(violations.is_empty(), "{:?} at agent {} broke the net: {:?}", result, id, violations);
+        }
+        result
+    }
+
+    // Errors are only returned before the net is changed. Once the agents and
+    // the wire between them are found the rewriting itself can not fail, it
+    // relies on the net being valid, see `validate`.
+    fn interact(&mut self, id : usize, rule : RuleKind) -> Result<RuleKind, NetError> {
+        let wid = self.agents.get(&id).ok_or(NetError::MissingAgent(id))?[0];
+
+        // Eta redexes are not active pairs, they are found from their lambda
+        if rule == RuleKind::Eta {
+            return Ok(match self.eta_redex(id) {
+                Some((app_id, controls)) => {
+                    self.eta_contract(id, app_id, controls);
+                    RuleKind::Eta
+                },
+                None => RuleKind::None
+            });
+        }
+
+        // Find the two agents and wire that are part of the rule
+        let incident = self.wires.get(&wid).ok_or(NetError::MissingWire(wid))?;
+        for end in [incident.source, incident.target].iter() {
+            if !self.agents.contains_key(end) {
+                return Err(NetError::MissingAgent(*end));
+            }
+        }
+        // Only agents that meet on their principal ports interact
+        let other = if incident.source == id { incident.target } else { incident.source };
+        if other == id || self.agent(other)[0] != wid {
+            return Ok(RuleKind::None);
+        }
+        let incident = self.remove_wire(wid).unwrap();
         let (agent, aid, partner, pid) = {
-            let source = self.agents.remove(&incident.source).unwrap();
-            let target = self.agents.remove(&incident.target).unwrap();
+            let source = self.remove_agent(incident.source);
+            let target = self.remove_agent(incident.target);
             if source.kind <= target.kind {
                 (source, incident.source, target, incident.target)
             } else {
@@ -630,92 +1492,145 @@ impl Net {
 
         // Determine a valid rule kind if possible
         let kind = match (agent.kind, partner.kind) {
+            | (_, AgentKind::Root)
+            | (AgentKind::Lambda, AgentKind::Lambda)
+            | (AgentKind::Application, AgentKind::Application)
+            | (AgentKind::Application, AgentKind::Free)
+            | (AgentKind::Free, AgentKind::Free)
+            | (AgentKind::Free, AgentKind::Lambda)
+            => RuleKind::None,
             | (_, AgentKind::Eraser)
             | (AgentKind::Eraser, _)
             => RuleKind::Erase,
             | (AgentKind::Application, AgentKind::Lambda)
             => RuleKind::Cancel,
-            | (AgentKind::Duplicator, AgentKind::Lambda)
-            | (AgentKind::Application, AgentKind::Duplicator)
-            => RuleKind::Duplicate,
-            | _
+            | (left, right) if left == right
             => match rule {
-                | RuleKind::Cancel
+                | RuleKind::Auto if agent.level == partner.level
+                => RuleKind::Cancel,
+                | RuleKind::Auto
                 | RuleKind::Duplicate
-                => rule,
+                => RuleKind::Duplicate,
+                | RuleKind::Cancel
+                => RuleKind::Cancel,
                 | _
                 => RuleKind::None
-            }
+            },
+            | _
+            => RuleKind::Duplicate
         };
-        
-        match kind {
-            RuleKind::Erase => {
-                // Determine who is erasing who
-                let (_eraser, _eid, partner, pid) = {
-                    if agent.kind == AgentKind::Eraser {
-                        (agent, aid, partner, pid)
-                    } else {
-                        (partner, pid, agent, aid)
-                    }
-                };
 
-                if partner.len() > 1 {
-                    let mut eraser_one = Agent::new(AgentKind::Eraser, vec![0]);
-                    eraser_one.x = partner.x;
-                    eraser_one.y = partner.y;
-                    eraser_one.fixed = partner.fixed;
-                    let eraser_two = eraser_one.clone();
-                    let e1id = self.add_agent(eraser_one);
-                    let e2id = self.add_agent(eraser_two);
-                    self.replace(0, partner[1], pid, e1id);
-                    self.replace(0, partner[2], pid, e2id);
-                }
-            },
-            RuleKind::Cancel => {
-                if agent[1] == agent[2] {
-                    self.wires.remove(&agent[1]);
-                    self.connect(pid, partner[1], pid, partner[2]);
-                } else if partner[1] == partner[2] {
-                    self.wires.remove(&partner[1]);
-                    self.connect(aid, agent[1], aid, agent[2]);
-                } else if agent[1] == partner[1] {
-                    self.wires.remove(&agent[1]);
-                    self.connect(aid, agent[2], pid, partner[2]);
-                } else if agent[2] == partner[2] {
-                    self.wires.remove(&agent[2]);
-                    self.connect(aid, agent[1], pid, partner[1]);
-                } else {
-                    self.connect(aid, agent[1], pid, partner[1]);
-                    self.connect(aid, agent[2], pid, partner[2]);
-                }
-            },
-            RuleKind::Duplicate => {
-                let agent1_id = self.add_agent(agent.clone());
-                let agent2_id = self.add_agent(agent.clone());
-                let partner1_id = self.add_agent(partner.clone());
-                let partner2_id = self.add_agent(partner.clone());
-
-                let wire1x2 = self.add_wire(Wire::new(agent1_id, partner2_id));
-                let wire2x1 = self.add_wire(Wire::new(agent2_id, partner1_id));
-                let wire1x1 = self.add_wire(Wire::new(agent1_id, partner1_id));
-                let wire2x2 = self.add_wire(Wire::new(agent2_id, partner2_id));
-
-                self.mut_agent(agent1_id).update(vec![partner[1], wire1x1, wire1x2]);
-                self.mut_agent(agent2_id).update(vec![partner[2], wire2x1, wire2x2]);
-                self.mut_agent(partner1_id).update(vec![agent[1], wire1x1, wire2x1]);
-                self.mut_agent(partner2_id).update(vec![agent[2], wire1x2, wire2x2]);
-
-                self.replace(0, partner[1], pid, agent1_id);
-                self.replace(0, partner[2], pid, agent2_id);
-                self.replace(0, agent[1], aid, partner1_id);
-                self.replace(0, agent[2], aid, partner2_id);
-            },
+        match kind {
+            RuleKind::Cancel => self.annihilate((aid, &agent), (pid, &partner)),
+            RuleKind::Erase | RuleKind::Duplicate => self.commute((aid, &agent), (pid, &partner)),
             _ => {
                 // Undo what has been done
-                self.wires.insert(wid, incident);
-                self.agents.insert(aid, agent);
-                self.agents.insert(pid, partner);
+                self.insert_wire(wid, incident);
+                self.insert_agent(aid, agent);
+                self.insert_agent(pid, partner);
             }
         }
+        Ok(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Agents with their ports and wires with their ends, taken as they are
+    fn net(agents : &[(usize, AgentKind, [usize; 3])], wires : &[(usize, usize, usize)]) -> Net {
+        let mut net = Net::new();
+        for (id, kind, ports) in agents {
+            net.agents.insert(*id, Agent { kind: *kind, level: 0, name: 0, wires: *ports });
+        }
+        for (id, source, target) in wires {
+            net.wires.insert(*id, Wire::new(*source, *target));
+        }
+        net
+    }
+
+    // A root above λx.x
+    const IDENTITY : [(usize, AgentKind, [usize; 3]); 2] = [
+        (1, AgentKind::Root, [1, 0, 0]),
+        (2, AgentKind::Lambda, [1, 2, 2])
+    ];
+
+    #[test]
+    fn validate_finds_each_violation() {
+        let wires = [(1, 1, 2), (2, 2, 2)];
+        assert_eq!(net(&IDENTITY, &wires).validate(), vec![]);
+
+        let with = |agent : (usize, AgentKind, [usize; 3]), wire : Option<(usize, usize, usize)>| {
+            let mut agents = IDENTITY.to_vec();
+            agents.push(agent);
+            let mut all = wires.to_vec();
+            all.extend(wire);
+            net(&agents, &all).validate()
+        };
+        // A port naming a wire that is not there
+        assert_eq!(with((3, AgentKind::Eraser, [9, 0, 0]), None),
+            vec![Violation::MissingWire(3, 0, 9)]);
+        // A port naming a wire between two other agents
+        assert_eq!(with((3, AgentKind::Eraser, [1, 0, 0]), None),
+            vec![Violation::DetachedPort(3, 0, 1)]);
+        // A wire that only one of its ends knows about
+        assert_eq!(with((3, AgentKind::Eraser, [3, 0, 0]), Some((3, 3, 1))),
+            vec![Violation::PortCount(3, 1, 0)]);
+        // A wire to an agent that is not there
+        assert_eq!(with((3, AgentKind::Eraser, [3, 0, 0]), Some((3, 3, 7))),
+            vec![Violation::MissingAgent(3, 7)]);
+        // More ports than the kind has
+        assert_eq!(with((3, AgentKind::Eraser, [3, 4, 0]), Some((3, 3, 3))),
+            vec![Violation::UnusedPort(3, 1, 4), Violation::PortCount(3, 3, 1)]);
+
+        let free = [(1, AgentKind::Free, [1, 0, 0]), (2, AgentKind::Eraser, [1, 0, 0])];
+        assert_eq!(net(&free, &[(1, 1, 2)]).validate(), vec![Violation::RootCount(0)]);
+        let roots = [(1, AgentKind::Root, [1, 0, 0]), (2, AgentKind::Root, [1, 0, 0])];
+        assert_eq!(net(&roots, &[(1, 1, 2)]).validate(), vec![Violation::RootCount(2)]);
+    }
+
+    #[test]
+    fn to_dot_marks_ports_pairs_and_wires() {
+        // (λx.x) y, the application is agent 2 and the lambda agent 3
+        let (net, _) = Net::from_text("R(r)\n@0(l, r, a)\nL0:x(l, b, b)\nF0:y(a)").unwrap();
+        let names = [(2, "y")].iter().cloned().collect();
+        let dot = net.to_dot(&names);
+        let lines : Vec<&str> = dot.lines().collect();
+        assert_eq!(lines.first(), Some(&"graph net {"));
+        assert_eq!(lines.last(), Some(&"}"));
+        // The principal port comes first, the agents of the active pair are bold
+        assert!(lines.contains(&r#"    1 [label="{<p0> ●|ℝ 0\n#1}", tooltip="root"];"#), "{}", dot);
+        assert!(lines.contains(&r#"    2 [label="{<p0> ●|@ 0\n#2|{<p1> 1|<p2> 2}}", tooltip="application", penwidth=3];"#), "{}", dot);
+        assert!(lines.contains(&r#"    3 [label="{<p0> ●|λ 0\n#3|{<p1> 1|<p2> 2}}", tooltip="lambda", penwidth=3];"#), "{}", dot);
+        assert!(lines.contains(&r#"    4 [label="{<p0> ●|• y 0\n#4}", tooltip="free"];"#), "{}", dot);
+        // Edges run between ports and carry their wire ids, the active one in red
+        assert!(lines.contains(&r#"    1:p0 -- 2:p1 [label="1"];"#), "{}", dot);
+        assert!(lines.contains(&r#"    2:p0 -- 3:p0 [label="2", penwidth=3, color=red];"#), "{}", dot);
+        assert!(lines.contains(&r#"    2:p2 -- 4:p0 [label="3"];"#), "{}", dot);
+        assert!(lines.contains(&r#"    3:p1 -- 3:p2 [label="4"];"#), "{}", dot);
+    }
+
+    #[test]
+    fn to_tree_reads_back_deep_terms() {
+        // The numeral 600, its variable f is shared by a chain of duplicators
+        let mut body = Tree::Var(2, 2);
+        for _ in 0..600 {
+            body = Tree::App(Box::new(Tree::Var(1, 1)), Box::new(body));
+        }
+        let numeral = Tree::Abs(1, 1, Box::new(Tree::Abs(2, 2, Box::new(body))));
+        assert_eq!(Net::from_tree(&numeral).to_tree().unwrap(), numeral);
+    }
+
+    #[test]
+    fn normalize_stops_at_the_size_limit() {
+        // (λx.x x x) λx.x x x keeps growing
+        let triple = |id| Tree::Abs(1, id, Box::new(Tree::App(
+            Box::new(Tree::App(Box::new(Tree::Var(1, id)), Box::new(Tree::Var(1, id)))),
+            Box::new(Tree::Var(1, id)))));
+        let mut net = Net::from_tree(&Tree::App(Box::new(triple(1)), Box::new(triple(2))));
+        let report = net.normalize(&Limits::size(100)).unwrap();
+        assert_eq!(report.outcome, Outcome::SizeLimit);
+        assert!(net.agents.len() > 100);
     }
 }
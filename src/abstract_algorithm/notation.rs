@@ -0,0 +1,312 @@
+// A text format for nets, one agent per line:
+//
+//     R(r)
+//     @0(f, r, a)
+//     L0:x(f, b, v)
+//
+// An agent is its header from `AgentKind::header`, its level (0 when left
+// out), a name for lambdas and free variables after a colon, and the names of
+// the wires on its ports, the principal one first. Names with characters other
+// than letters, digits, `_` and `'` are quoted, like `F0:"+"(a)`, with `\"` and
+// `\\` inside the quotes standing for `"` and `\`. Every wire name appears on
+// exactly two ports and there is exactly one root. `#` starts a comment.
+use std::collections::HashMap;
+use std::fmt;
+
+use super::net::AgentKind;
+use utils::error_json;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationErrorKind {
+    UnknownAgent(char),
+    MissingParen,
+    MissingWire,
+    MissingName,
+    UnclosedQuote,
+    MisplacedName(AgentKind),
+    LevelTooLarge,
+    PortCount(AgentKind, usize), // The kind and how many ports it was given
+    WireCount(String, usize),    // The wire and how many ports name it
+    RootCount(usize)
+}
+
+impl fmt::Display for NotationErrorKind {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationErrorKind::UnknownAgent(c) => write!(f, "{:?} does not start an agent", c),
+            NotationErrorKind::MissingParen => write!(f, "expected a parenthesis"),
+            NotationErrorKind::MissingWire => write!(f, "expected a wire name"),
+            NotationErrorKind::MissingName => write!(f, "expected a name after the colon"),
+            NotationErrorKind::UnclosedQuote => write!(f, "quoted name is never closed"),
+            NotationErrorKind::MisplacedName(kind) => write!(f, "{:?} agents have no name", kind),
+            NotationErrorKind::LevelTooLarge => write!(f, "level is too large"),
+            NotationErrorKind::PortCount(kind, count)
+                => write!(f, "{:?} agents have {} ports, not {}", kind, kind.arity(), count),
+            NotationErrorKind::WireCount(wire, count)
+                => write!(f, "wire {} should be on two ports, not {}", wire, count),
+            NotationErrorKind::RootCount(count) => write!(f, "the net has {} roots instead of one", count)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotationError {
+    pub kind : NotationErrorKind,
+    pub line : usize, // Both counted from 1, the column in characters
+    pub column : usize,
+    text : String
+}
+
+impl NotationError {
+    fn new(input : &str, kind : NotationErrorKind, offset : usize) -> NotationError {
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..].find('\n').map_or(input.len(), |i| offset + i);
+        let line = 1 + input[..line_start].matches('\n').count();
+        let column = 1 + input[line_start..offset].chars().count();
+        let text = input[line_start..line_end].trim_end_matches('\r').to_string();
+        NotationError { kind, line, column, text }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self.kind {
+            NotationErrorKind::UnknownAgent(_) => "UnknownAgent",
+            NotationErrorKind::MissingParen => "MissingParen",
+            NotationErrorKind::MissingWire => "MissingWire",
+            NotationErrorKind::MissingName => "MissingName",
+            NotationErrorKind::UnclosedQuote => "UnclosedQuote",
+            NotationErrorKind::MisplacedName(_) => "MisplacedName",
+            NotationErrorKind::LevelTooLarge => "LevelTooLarge",
+            NotationErrorKind::PortCount(_, _) => "PortCount",
+            NotationErrorKind::WireCount(_, _) => "WireCount",
+            NotationErrorKind::RootCount(_) => "RootCount"
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        error_json(self.kind(), &self.kind.to_string(), json!({
+            "line": self.line,
+            "column": self.column,
+            "text": self.to_string()
+        }))
+    }
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let padding : String = self.text.chars()
+            .take(self.column - 1)
+            .map(|x| if x == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(f, "error: {} at {}:{}", self.kind, self.line, self.column)?;
+        writeln!(f, "{}", self.text)?;
+        write!(f, "{}^", padding)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentNotation {
+    pub kind : AgentKind,
+    pub level : usize,
+    pub name : Option<String>,
+    pub wires : Vec<String>
+}
+
+impl fmt::Display for AgentNotation {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind.header())?;
+        if self.kind != AgentKind::Root {
+            write!(f, "{}", self.level)?;
+        }
+        if let Some(name) = &self.name {
+            if !name.is_empty() && name.chars().all(is_name) {
+                write!(f, ":{}", name)?;
+            } else {
+                write!(f, ":\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))?;
+            }
+        }
+        write!(f, "({})", self.wires.join(", "))
+    }
+}
+
+pub fn print(agents : &[AgentNotation]) -> String {
+    let mut result = String::new();
+    for agent in agents {
+        result.push_str(&agent.to_string());
+        result.push('\n');
+    }
+    result
+}
+
+// The n-th wire name of a printed net: a, b, ..., z, aa, ab, ...
+pub fn wire_name(mut index : usize) -> String {
+    let mut result = vec![];
+    loop {
+        result.push((b'a' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    result.iter().rev().collect()
+}
+
+fn is_name(c : char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+struct Scanner<'a> {
+    input : &'a str,
+    offset : usize
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.offset..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn take_while<F : Fn(char) -> bool>(&mut self, test : F) -> &'a str {
+        let start = self.offset;
+        while self.peek().is_some_and(&test) {
+            self.bump();
+        }
+        &self.input[start..self.offset]
+    }
+
+    fn error(&self, kind : NotationErrorKind) -> NotationError {
+        NotationError::new(self.input, kind, self.offset)
+    }
+
+    fn expect(&mut self, c : char) -> Result<(), NotationError> {
+        self.skip_blanks();
+        if self.peek() == Some(c) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(NotationErrorKind::MissingParen))
+        }
+    }
+
+    // A name between quotes, the scanner is on the opening one
+    fn quoted(&mut self) -> Result<String, NotationError> {
+        let start = self.offset;
+        self.bump();
+        let mut name = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(name),
+                Some('\\') => match self.bump() {
+                    Some(c) => name.push(c),
+                    None => break
+                },
+                Some(c) => name.push(c),
+                None => break
+            }
+        }
+        Err(NotationError::new(self.input, NotationErrorKind::UnclosedQuote, start))
+    }
+
+    fn agent(&mut self) -> Result<AgentNotation, NotationError> {
+        let start = self.offset;
+        let header = self.bump().expect("Only called before a character.");
+        let kind = AgentKind::from_header(header)
+            .ok_or_else(|| NotationError::new(self.input, NotationErrorKind::UnknownAgent(header), start))?;
+
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        let level = if digits.is_empty() { 0 } else {
+            digits.parse().map_err(|_| self.error(NotationErrorKind::LevelTooLarge))?
+        };
+
+        let name = if self.peek() == Some(':') {
+            if kind != AgentKind::Lambda && kind != AgentKind::Free {
+                return Err(self.error(NotationErrorKind::MisplacedName(kind)));
+            }
+            self.bump();
+            if self.peek() == Some('"') {
+                Some(self.quoted()?)
+            } else {
+                let name = self.take_while(is_name);
+                if name.is_empty() {
+                    return Err(self.error(NotationErrorKind::MissingName));
+                }
+                Some(name.to_string())
+            }
+        } else {
+            None
+        };
+
+        self.expect('(')?;
+        let mut wires = vec![];
+        loop {
+            self.skip_blanks();
+            let wire = self.take_while(is_name);
+            if wire.is_empty() {
+                return Err(self.error(NotationErrorKind::MissingWire));
+            }
+            wires.push(wire.to_string());
+            self.skip_blanks();
+            match self.peek() {
+                Some(',') => { self.bump(); },
+                Some(')') => { self.bump(); break; },
+                _ => return Err(self.error(NotationErrorKind::MissingParen))
+            }
+        }
+        if wires.len() != kind.arity() {
+            return Err(NotationError::new(self.input, NotationErrorKind::PortCount(kind, wires.len()), start));
+        }
+        Ok(AgentNotation { kind, level, name, wires })
+    }
+}
+
+pub fn parse(input : &str) -> Result<Vec<AgentNotation>, NotationError> {
+    let mut scanner = Scanner { input, offset: 0 };
+    let mut agents = vec![];
+    let mut starts = vec![];
+    loop {
+        scanner.skip_blanks();
+        if scanner.peek().is_none() {
+            break;
+        }
+        starts.push(scanner.offset);
+        agents.push(scanner.agent()?);
+    }
+
+    // Errors about the whole net point at the first agent they concern
+    let mut wires : HashMap<&str, (usize, usize)> = HashMap::new();
+    for (index, agent) in agents.iter().enumerate() {
+        for wire in &agent.wires {
+            wires.entry(wire.as_str()).or_insert((index, 0)).1 += 1;
+        }
+    }
+    let mut miscounted : Vec<_> = wires.iter().filter(|(_, (_, count))| *count != 2).collect();
+    miscounted.sort_by_key(|(wire, (index, _))| (*index, *wire));
+    if let Some((wire, (index, count))) = miscounted.first() {
+        let kind = NotationErrorKind::WireCount(wire.to_string(), *count);
+        return Err(NotationError::new(input, kind, starts[*index]));
+    }
+    let roots : Vec<_> = (0..agents.len()).filter(|i| agents[*i].kind == AgentKind::Root).collect();
+    if roots.len() != 1 {
+        let offset = roots.get(1).map_or(input.len(), |i| starts[*i]);
+        return Err(NotationError::new(input, NotationErrorKind::RootCount(roots.len()), offset));
+    }
+    Ok(agents)
+}
@@ -2,28 +2,51 @@
 #[derive(Debug, Copy, Clone)]
 pub enum Token {
     Name(usize, usize),
+    Number(usize, usize), // A name made of decimal digits only
     OpenParen,
     CloseParen,
     Lambda,
-    Dot
+    Dot,
+    Equals,
+    Semicolon
 }
 
+// Byte offsets of a token in the input, end exclusive
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Span {
+    pub start : usize,
+    pub end : usize
+}
+
+// The Unicode lambda, accepted in place of a backslash
+const LAMBDA : &[u8] = "λ".as_bytes();
+
 pub struct Lexer<'a> {
-    input : &'a [u8], // ASCII values only for now
+    input : &'a [u8], // ASCII values only, apart from the lambda
     location : usize,
-    history : Vec<Token>,
-    history_index : usize
+    history : Vec<(Token, Span)>,
+    history_index : usize,
+    exhausted : bool
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input : &[u8]) -> Lexer {
-        Lexer { input, location: 0, history : vec![], history_index: 0 }
+    pub fn new(input : &[u8]) -> Lexer<'_> {
+        Lexer { input, location: 0, history : vec![], history_index: 0, exhausted: false }
     }
 }
 
 impl<'a> Lexer<'a> {
     pub fn backtrack(&mut self, n : usize) {
         self.history_index = std::cmp::max(0, self.history_index - n);
+        self.exhausted = false;
+    }
+
+    // The span of the last token returned, or the end of the input once it is exhausted
+    pub fn span(&self) -> Span {
+        match self.history_index.checked_sub(1).map(|i| self.history[i]) {
+            Some((_, span)) if !self.exhausted => span,
+            _ => Span { start: self.input.len(), end: self.input.len() }
+        }
     }
 }
 
@@ -32,43 +55,57 @@ impl<'a> Iterator for Lexer<'a> {
 
     fn next(&mut self) -> Option<Token> {
         if self.history_index < self.history.len() {
-            let result = Some(self.history[self.history_index]);
+            let (token, _) = self.history[self.history_index];
             self.history_index += 1;
-            result
+            self.exhausted = false;
+            Some(token)
         } else {
             while self.input
                 .get(self.location)
-                .map_or(false, |x| x.is_ascii_whitespace())
+                .is_some_and(|x| x.is_ascii_whitespace())
             {
                 self.location += 1;
             }
 
+            let start = self.location;
             let mut difference = 0;
             let result = self.input.get(self.location)
-                .map_or(None,
-                |x| Some(match x {
+                .map(|x| match x {
                     b'(' => { self.location += 1; Token::OpenParen },
                     b')' => { self.location += 1; Token::CloseParen },
                     b'\\' => { self.location += 1; Token::Lambda },
                     b'.' => { self.location += 1; Token::Dot },
+                    b'=' => { self.location += 1; Token::Equals },
+                    b';' => { self.location += 1; Token::Semicolon },
+                    _ if self.input[self.location..].starts_with(LAMBDA) => {
+                        self.location += LAMBDA.len();
+                        Token::Lambda
+                    },
                     _ => {
                         difference += 1;
                         while let Some(i) = self.input.get(self.location + difference) {
                             match i {
-                                b'(' | b')' | b'\\' | b'.' => break,
+                                b'(' | b')' | b'\\' | b'.' | b'=' | b';' => break,
                                 x if x.is_ascii_whitespace() => break,
+                                _ if self.input[self.location + difference..].starts_with(LAMBDA) => break,
                                 _ => { difference += 1 }
                             }
                         }
-                        let result = Token::Name(self.location, difference);
+                        let word = &self.input[self.location..self.location + difference];
+                        let result = if word.iter().all(|x| x.is_ascii_digit()) {
+                            Token::Number(self.location, difference)
+                        } else {
+                            Token::Name(self.location, difference)
+                        };
                         self.location += difference;
                         result
                     }
-                }));
+                });
             if let Some(token) = result {
-                self.history.push(token);
+                self.history.push((token, Span { start, end: self.location }));
                 self.history_index = self.history.len();
             }
+            self.exhausted = result.is_none();
             result
         }
     }
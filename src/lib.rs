@@ -5,21 +5,18 @@ extern crate wasm_bindgen;
 extern crate serde_json;
 #[macro_use]
 extern crate serde_derive;
-#[macro_use]
-extern crate lazy_static;
 
 mod utils;
-mod abstract_algorithm;
-mod lexer;
-mod typical;
+pub mod abstract_algorithm;
+pub mod lexer;
+pub mod limits;
+pub mod typical;
 
 use abstract_algorithm::*;
+use limits::{Limits, DEFAULT_SIZE};
+use typical::Rules;
 use wasm_bindgen::prelude::*;
-use std::sync::Mutex;
-
-lazy_static! {
-    static ref NET : Mutex<Net> = Mutex::new(Net::new());
-}
+use std::collections::HashMap;
 
 #[wasm_bindgen]
 extern {
@@ -27,57 +24,192 @@ extern {
     fn log(s: &str);
 }
 
-#[wasm_bindgen]
-pub fn update(json : &str) {
-    let data = serde_json::from_str::<NodeDataArray>(json).ok().unwrap();
-    let mut net = NET.try_lock().expect("Locking failed.");
-    net.update_from_json(data);
+// Errors reach JavaScript as the text of `utils::error_json`
+fn error(kind : &str, message : &str, id : Option<usize>) -> JsValue {
+    JsValue::from_str(utils::error_json(kind, message, json!({ "id": id })).as_str())
 }
 
-#[wasm_bindgen]
-pub fn reduce(index : usize, requested_kind : &str) -> String {
-    let mut net = NET.try_lock().expect("Locking failed.");
-    let kind = match requested_kind {
-        "auto" => RuleKind::Auto,
-        "cancel" => RuleKind::Cancel,
-        "duplicate" => RuleKind::Duplicate,
-        "erase" => RuleKind::Erase,
-        _ => RuleKind::None
-    };
-    net.reduction_step(index, kind);
-    log(format!("{:?}", *net).as_str());
-    net.to_json()
+fn net_error(error : NetError) -> JsValue {
+    JsValue::from_str(error.to_json().as_str())
 }
+
+// A net with the names of its free variables, its own history and where its
+// agents are drawn. Several of them can be open at once, to show terms side
+// by side or to follow a reduction down two paths after a `fork`.
 #[wasm_bindgen]
-pub fn rebuild(json : &str) {
-    let data = serde_json::from_str::<FromJsonData>(json)
-		.ok()
-		.expect("Deserialization failed.");
-    let mut net = NET.try_lock().expect("Locking failed.");
-    *net = Net::from_json(data);
-	log(format!("{:?}",*net).as_str());
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    net : Net,
+    names : HashMap<isize, String>,
+    layout : Layout
+}
+
+impl Session {
+    fn names(&self) -> HashMap<isize, &str> {
+        typical::borrow_names(&self.names)
+    }
 }
 
 #[wasm_bindgen]
-pub fn load_net(term : &str) -> String {
-    let mut net = NET.try_lock().expect("Locking failed.");
-    let input = term.as_bytes();
-    let lexer = lexer::Lexer::new(input);
-    let mut parser = typical::Parser::new(input, lexer);
-    let tree_result = parser.parse();
-    let names = parser.names_map();
-
-    match tree_result {
-        Ok(mut tree) => {
-            tree.canonicalize_names();
-            *net = abstract_algorithm::Net::from_tree(&tree);
-            log(format!("{:?}", *net).as_str());
-            let result = net.to_json();
-            log(format!("{}", result).as_str());
-            result
-        },
-        Err(e) => {
-            "Error".to_string()
+impl Session {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Session {
+        utils::set_panic_hook();
+        Session::default()
+    }
+
+    // A copy of the session as it is now, undoing in one does not change the
+    // other
+    pub fn fork(&self) -> Session {
+        self.clone()
+    }
+
+    pub fn to_json(&self) -> String {
+        self.layout.to_json(&self.net, &self.names())
+    }
+
+    pub fn to_dot(&self) -> String {
+        self.net.to_dot(&self.names())
+    }
+
+    pub fn to_text(&self) -> String {
+        self.net.to_text(&self.names())
+    }
+
+    // Replaces the net with one written in the text notation
+    pub fn load_text(&mut self, text : &str) -> Result<String, JsValue> {
+        let (net, names) = Net::from_text(text)
+            .map_err(|e| JsValue::from_str(e.to_json().as_str()))?;
+        self.net = net;
+        self.names = names;
+        self.layout = Layout::new();
+        Ok(self.to_json())
+    }
+
+    pub fn update(&mut self, json : &str) -> Result<String, JsValue> {
+        let data = serde_json::from_str::<NodeDataArray>(json)
+            .map_err(|e| error("Deserialization", e.to_string().as_str(), None))?;
+        self.layout.update_from_json(&self.net, data).map_err(net_error)?;
+        Ok(self.to_json())
+    }
+
+    pub fn reduce(&mut self, index : usize, requested_kind : &str) -> Result<String, JsValue> {
+        let kind = match requested_kind {
+            "auto" => RuleKind::Auto,
+            "cancel" => RuleKind::Cancel,
+            "duplicate" => RuleKind::Duplicate,
+            "erase" => RuleKind::Erase,
+            "eta" => RuleKind::Eta,
+            _ => return Err(error("UnknownRule", format!("There is no rule {:?}.", requested_kind).as_str(), None))
+        };
+        self.net.reduction_step(index, kind).map_err(net_error)?;
+        self.layout.follow(&self.net);
+        Ok(self.to_json())
+    }
+
+    pub fn normalize(&mut self, limit : usize, eta : bool) -> Result<String, JsValue> {
+        let rules = if eta { Rules::BetaEta } else { Rules::Beta };
+        let report = self.net.normalize_with(rules, &Limits::new(Some(limit), None, Some(DEFAULT_SIZE))).map_err(net_error)?;
+        self.layout.follow(&self.net);
+        log(format!("{:?}", report).as_str());
+        Ok(self.to_json())
+    }
+
+    // Undoing or redoing when there is nothing to leaves the net as it is
+    pub fn undo(&mut self) -> String {
+        self.net.undo();
+        self.layout.follow(&self.net);
+        self.to_json()
+    }
+
+    pub fn redo(&mut self) -> String {
+        self.net.redo();
+        self.layout.follow(&self.net);
+        self.to_json()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.net.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.net.can_redo()
+    }
+
+    // An inconsistent net is refused and the current one is kept
+    pub fn rebuild(&mut self, json : &str) -> Result<String, JsValue> {
+        let data = serde_json::from_str::<FromJsonData>(json)
+            .map_err(|e| error("Deserialization", e.to_string().as_str(), None))?;
+        let (net, layout) = Layout::from_json(data).map_err(net_error)?;
+        self.net = net;
+        self.layout = layout;
+        Ok(self.to_json())
+    }
+
+    pub fn load(&mut self, term : &str) -> Result<String, JsValue> {
+        match typical::parse_with_prelude(term) {
+            Ok((tree, names)) => {
+                self.names = names;
+                self.net = abstract_algorithm::Net::from_tree(&tree);
+                self.layout = Layout::new();
+                Ok(self.to_json())
+            },
+            Err(error) => {
+                log(error.to_string().as_str());
+                Err(JsValue::from_str(error.to_json().as_str()))
+            }
         }
     }
+
+    pub fn read_back(&self) -> Result<String, JsValue> {
+        match self.net.to_tree() {
+            Ok(tree) => Ok(tree.to_string(&self.names())),
+            Err(e) => Err(JsValue::from_str(e.to_json().as_str()))
+        }
+    }
+
+    pub fn read_value(&self) -> Result<String, JsValue> {
+        match self.net.to_value() {
+            Ok(Some(value)) => Ok(value.to_string()),
+            Ok(None) => Ok("None".to_string()),
+            Err(e) => Err(JsValue::from_str(e.to_json().as_str()))
+        }
+    }
+}
+
+// Reduces a term on its own, outside of the net, and lists every term on the
+// way with the redexes contracted in it between `open` and `close`
+#[wasm_bindgen]
+pub fn trace(term : &str, requested_strategy : &str, limit : usize, open : &str, close : &str) -> Result<String, JsValue> {
+    let strategy = match requested_strategy {
+        "normal" => typical::Strategy::NormalOrder,
+        "applicative" => typical::Strategy::ApplicativeOrder,
+        "name" => typical::Strategy::CallByName,
+        "value" => typical::Strategy::CallByValue,
+        "need" => typical::Strategy::CallByNeed,
+        "parallel" => typical::Strategy::Parallel,
+        _ => return Err(error("UnknownStrategy", format!("There is no strategy {:?}.", requested_strategy).as_str(), None))
+    };
+    let (tree, names) = match typical::parse_with_prelude(term) {
+        Ok(result) => result,
+        Err(error) => return Err(JsValue::from_str(error.to_json().as_str()))
+    };
+    let names = typical::borrow_names(&names);
+
+    let mut trace = tree.trace(strategy, &Limits::new(Some(limit), None, Some(DEFAULT_SIZE)));
+    let mut steps = vec![];
+    for step in trace.by_ref() {
+        steps.push(json!({
+            "term": step.tree.to_string_marked(&names, &step.redexes, open, close),
+            "redexes": step.redexes.iter()
+                .map(|path| path.iter().map(|branch| format!("{:?}", branch).to_lowercase()).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        }));
+    }
+    let result = json!({
+        "steps": steps,
+        "result": trace.tree.to_string(&names),
+        "outcome": format!("{:?}", trace.outcome().expect("The trace is done."))
+    });
+    Ok(result.to_string())
 }
@@ -0,0 +1,78 @@
+use std::time::{Instant, Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Normal,
+    StepLimit,
+    TimeLimit,
+    SizeLimit,
+    Stuck
+}
+
+// A size budget that still leaves room for large normal forms, but stops a
+// term that keeps growing long before it takes all of the memory
+pub const DEFAULT_SIZE : usize = 1_000_000;
+
+// The size counts the nodes of a term or the agents of a net
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub steps : Option<usize>,
+    pub time : Option<Duration>,
+    pub size : Option<usize>
+}
+
+impl Limits {
+    pub fn new(steps : Option<usize>, time : Option<Duration>, size : Option<usize>) -> Limits {
+        Limits { steps, time, size }
+    }
+
+    pub fn steps(steps : usize) -> Limits {
+        Limits::new(Some(steps), None, None)
+    }
+
+    pub fn time(time : Duration) -> Limits {
+        Limits::new(None, Some(time), None)
+    }
+
+    pub fn size(size : usize) -> Limits {
+        Limits::new(None, None, Some(size))
+    }
+
+    // The clock is only read when there is a time budget, `Instant` is not
+    // available when running inside the browser.
+    pub fn start(&self) -> Option<Instant> {
+        self.time.map(|_| Instant::now())
+    }
+
+    // What is left of the limits after taking `steps` since `start`
+    pub fn remaining(&self, steps : usize, start : &Option<Instant>) -> Limits {
+        Limits {
+            steps: self.steps.map(|limit| limit.saturating_sub(steps)),
+            time: match (self.time, start) {
+                (Some(limit), Some(start)) => Some(limit.checked_sub(start.elapsed()).unwrap_or_default()),
+                (time, _) => time
+            },
+            size: self.size
+        }
+    }
+
+    pub fn check(&self, steps : usize, start : &Option<Instant>) -> Option<Outcome> {
+        if self.steps.is_some_and(|limit| steps >= limit) {
+            return Some(Outcome::StepLimit);
+        }
+        match (self.time, start) {
+            (Some(limit), Some(start)) if start.elapsed() > limit
+                => Some(Outcome::TimeLimit),
+            _ => None
+        }
+    }
+
+    // The size is only worked out when there is a size budget, for a term
+    // that takes a walk over all of it
+    pub fn check_size<F : FnOnce() -> usize>(&self, size : F) -> Option<Outcome> {
+        match self.size {
+            Some(limit) if size() > limit => Some(Outcome::SizeLimit),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,300 @@
+extern crate olette;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use olette::abstract_algorithm::{Net, ReductionReport};
+use olette::limits::{Limits, DEFAULT_SIZE};
+use olette::typical::{borrow_names, parse_with_prelude, Rules, Strategy};
+
+const USAGE : &str = "\
+Usage: olette [options] [file ...]
+
+Reduces every file, or the terms given with -e, both as a tree and as a net
+and prints the normal forms with their step counts and timings. Files ending
+in .net hold a net in the text notation and are only reduced as a net.
+Without any it reads one term per line, as a prompt when the input is a
+terminal.
+
+Options:
+  -e, --eval TERM        Reduce TERM, can be given several times
+  -s, --strategy NAME    normal, applicative, name, value, need or parallel
+                         for the tree, normal by default
+      --steps N          Stop each reduction after N steps, 1000000 by default
+      --time SECONDS     Stop each reduction after SECONDS
+      --size N           Stop each reduction once the term has more than N
+                         nodes or the net more than N agents, 1000000 by
+                         default
+      --eta              Contract eta redexes as well
+      --trace            Print every step of the tree reduction, with the
+                         redexes it contracts in brackets
+      --dot              Print the net once reduced as a Graphviz graph
+      --text             Print the net once reduced in the text notation
+  -h, --help             Print this message
+
+At the prompt, lines starting with a colon change the options: :strategy NAME,
+:steps N, :time SECONDS, :size N, :eta on|off, :trace on|off, :dot on|off,
+:text on|off, :help and :quit.";
+
+const DEFAULT_STEPS : usize = 1_000_000;
+
+struct Options {
+    strategy : Strategy,
+    rules : Rules,
+    limits : Limits,
+    trace : bool,
+    dot : bool,
+    text : bool
+}
+
+enum Input {
+    Term(String),
+    File(String)
+}
+
+fn strategy(name : &str) -> Result<Strategy, String> {
+    match name {
+        "normal" => Ok(Strategy::NormalOrder),
+        "applicative" => Ok(Strategy::ApplicativeOrder),
+        "name" => Ok(Strategy::CallByName),
+        "value" => Ok(Strategy::CallByValue),
+        "need" => Ok(Strategy::CallByNeed),
+        "parallel" => Ok(Strategy::Parallel),
+        _ => Err(format!("There is no strategy {:?}.", name))
+    }
+}
+
+fn steps(value : &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("{:?} is not a number of steps.", value))
+}
+
+fn time(value : &str) -> Result<Duration, String> {
+    value.parse::<f64>().ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+        .ok_or_else(|| format!("{:?} is not a number of seconds.", value))
+}
+
+fn size(value : &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("{:?} is not a size.", value))
+}
+
+fn milliseconds(elapsed : Duration) -> String {
+    format!("{:.3} ms", elapsed.as_secs_f64() * 1000.0)
+}
+
+fn interactions(report : &ReductionReport) -> String {
+    report.interactions.iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(kind, count)| format!("{} {}", format!("{:?}", kind).to_lowercase(), count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Reduces one term both ways, false when it could not be
+fn run(source : &str, options : &Options) -> bool {
+    let (tree, names) = match parse_with_prelude(source) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
+    let names = borrow_names(&names);
+    println!("term: {}", tree.to_string(&names));
+    if options.trace {
+        // Eta redexes are not traced, only the beta steps before them
+        for (index, step) in tree.trace(options.strategy, &options.limits).enumerate() {
+            println!("{:>4}: {}", index + 1, step.tree.to_string_marked(&names, &step.redexes, "[", "]"));
+        }
+    }
+
+    let start = Instant::now();
+    let reduction = tree.reduce_with(options.strategy, options.rules, &options.limits);
+    let elapsed = start.elapsed();
+    println!("tree: {}", reduction.tree.to_string(&names));
+    println!("      {} beta, {} eta steps, {}, {:?}",
+        reduction.steps, reduction.eta_steps, milliseconds(elapsed), reduction.outcome);
+
+    let start = Instant::now();
+    let net = Net::from_tree(&tree);
+    let built = start.elapsed();
+    run_net(net, &names, built, options)
+}
+
+// Reduces a net read from the text notation
+fn run_text(source : &str, options : &Options) -> bool {
+    let start = Instant::now();
+    let (net, names) = match Net::from_text(source) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
+    let built = start.elapsed();
+    run_net(net, &borrow_names(&names), built, options)
+}
+
+fn run_net(mut net : Net, names : &HashMap<isize, &str>, built : Duration, options : &Options) -> bool {
+    let start = Instant::now();
+    let report = match net.normalize_with(options.rules, &options.limits) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return false;
+        }
+    };
+    let elapsed = start.elapsed();
+    let success = match net.to_tree() {
+        Ok(result) => { println!("net:  {}", result.to_string(names)); true },
+        Err(error) => { eprintln!("net:  {}", error); false }
+    };
+    println!("      {} interactions ({}), built in {}, reduced in {}, {:?}",
+        report.total(), interactions(&report), milliseconds(built), milliseconds(elapsed), report.outcome);
+    if options.dot {
+        print!("{}", net.to_dot(names));
+    }
+    if options.text {
+        print!("{}", net.to_text(names));
+    }
+    success
+}
+
+// Applies a line starting with a colon, Ok(false) to quit
+fn command(line : &str, options : &mut Options) -> Result<bool, String> {
+    let mut words = line[1..].split_whitespace();
+    let result = match (words.next(), words.next()) {
+        (Some("quit"), None) | (Some("q"), None) => return Ok(false),
+        (Some("help"), None) => { println!("{}", USAGE); Ok(()) },
+        (Some("strategy"), Some(name)) => strategy(name).map(|strategy| options.strategy = strategy),
+        (Some("steps"), Some(value)) => steps(value).map(|steps| options.limits.steps = Some(steps)),
+        (Some("time"), Some(value)) => time(value).map(|time| options.limits.time = Some(time)),
+        (Some("size"), Some(value)) => size(value).map(|size| options.limits.size = Some(size)),
+        (Some("eta"), Some("on")) => { options.rules = Rules::BetaEta; Ok(()) },
+        (Some("eta"), Some("off")) => { options.rules = Rules::Beta; Ok(()) },
+        (Some("trace"), Some("on")) => { options.trace = true; Ok(()) },
+        (Some("trace"), Some("off")) => { options.trace = false; Ok(()) },
+        (Some("dot"), Some("on")) => { options.dot = true; Ok(()) },
+        (Some("dot"), Some("off")) => { options.dot = false; Ok(()) },
+        (Some("text"), Some("on")) => { options.text = true; Ok(()) },
+        (Some("text"), Some("off")) => { options.text = false; Ok(()) },
+        _ => Err(format!("Unknown command {:?}, see :help.", line))
+    };
+    result.map(|_| true)
+}
+
+fn repl(options : &mut Options) -> bool {
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    let mut success = true;
+    let mut lines = stdin.lock().lines();
+    loop {
+        if prompt {
+            print!("> ");
+            io::stdout().flush().expect("Failed to write the prompt.");
+        }
+        let line = match lines.next() {
+            Some(line) => line.expect("Failed to read line."),
+            None => break
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with(':') {
+            match command(line, options) {
+                Ok(true) => continue,
+                Ok(false) => break,
+                Err(message) => {
+                    eprintln!("error: {}", message);
+                    success = false;
+                    continue;
+                }
+            }
+        }
+        success &= run(line, options);
+        println!();
+    }
+    success
+}
+
+fn arguments() -> Result<(Options, Vec<Input>), String> {
+    let mut options = Options {
+        strategy: Strategy::NormalOrder,
+        rules: Rules::Beta,
+        limits: Limits::new(Some(DEFAULT_STEPS), None, Some(DEFAULT_SIZE)),
+        trace: false,
+        dot: false,
+        text: false
+    };
+    let mut inputs = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value.", arg));
+        match arg.as_str() {
+            "-e" | "--eval" => inputs.push(Input::Term(value()?)),
+            "-s" | "--strategy" => options.strategy = strategy(&value()?)?,
+            "--steps" => options.limits.steps = Some(steps(&value()?)?),
+            "--time" => options.limits.time = Some(time(&value()?)?),
+            "--size" => options.limits.size = Some(size(&value()?)?),
+            "--eta" => options.rules = Rules::BetaEta,
+            "--trace" => options.trace = true,
+            "--dot" => options.dot = true,
+            "--text" => options.text = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {:?}.", arg)),
+            _ => inputs.push(Input::File(arg))
+        }
+    }
+    Ok((options, inputs))
+}
+
+fn main() {
+    let (mut options, inputs) = match arguments() {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let success = if inputs.is_empty() {
+        repl(&mut options)
+    } else {
+        let mut success = true;
+        for (index, input) in inputs.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            success &= match input {
+                Input::Term(term) => run(term, &options),
+                Input::File(path) => match fs::read_to_string(path) {
+                    Ok(source) => {
+                        println!("file: {}", path);
+                        if path.ends_with(".net") {
+                            run_text(&source, &options)
+                        } else {
+                            run(&source, &options)
+                        }
+                    },
+                    Err(error) => {
+                        eprintln!("error: {}: {}", path, error);
+                        false
+                    }
+                }
+            };
+        }
+        success
+    };
+    if !success {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use typical::Tree;
+use limits::{Limits, Outcome};
+
+// A term where bound variables are the number of binders between them and
+// their own, counted from 0. Binder names are only kept to convert back to a
+// `Tree`, equality and hashing ignore them, so terms equal up to renaming
+// are equal here.
+#[derive(Debug, Clone)]
+pub enum DeBruijn {
+    Var(usize),
+    Free(String),
+    Abs(String, Box<DeBruijn>),
+    App(Box<DeBruijn>, Box<DeBruijn>)
+}
+
+#[derive(Debug, Clone)]
+pub struct NamelessReduction {
+    pub term : DeBruijn,
+    pub steps : usize,
+    pub outcome : Outcome
+}
+
+impl PartialEq for DeBruijn {
+    fn eq(&self, other : &DeBruijn) -> bool {
+        match (self, other) {
+            (DeBruijn::Var(x), DeBruijn::Var(y)) => x == y,
+            (DeBruijn::Free(x), DeBruijn::Free(y)) => x == y,
+            (DeBruijn::Abs(_, x), DeBruijn::Abs(_, y)) => x == y,
+            (DeBruijn::App(a, b), DeBruijn::App(c, d)) => a == c && b == d,
+            _ => false
+        }
+    }
+}
+
+impl Eq for DeBruijn {}
+
+impl Hash for DeBruijn {
+    fn hash<H : Hasher>(&self, state : &mut H) {
+        match self {
+            DeBruijn::Var(x) => { 0.hash(state); x.hash(state); },
+            DeBruijn::Free(x) => { 1.hash(state); x.hash(state); },
+            DeBruijn::Abs(_, body) => { 2.hash(state); body.hash(state); },
+            DeBruijn::App(left, right) => { 3.hash(state); left.hash(state); right.hash(state); }
+        }
+    }
+}
+
+impl fmt::Display for DeBruijn {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_helper(f, true)
+    }
+}
+
+impl DeBruijn {
+
+    // An abstraction reaches as far right as it can, so one that is followed
+    // by more of an application goes in parentheses
+    fn fmt_helper(&self, f : &mut fmt::Formatter, last : bool) -> fmt::Result {
+        match self {
+            DeBruijn::Var(x) => write!(f, "{}", x),
+            DeBruijn::Free(x) => write!(f, "{}", x),
+            DeBruijn::Abs(_, body) => {
+                write!(f, "λ.")?;
+                body.fmt_helper(f, true)
+            },
+            DeBruijn::App(left, right) => {
+                match **left {
+                    DeBruijn::Abs(_, _) => write!(f, "({})", left)?,
+                    _ => left.fmt_helper(f, false)?
+                }
+                match **right {
+                    DeBruijn::App(_, _) => write!(f, " ({})", right),
+                    DeBruijn::Abs(_, _) if !last => write!(f, " ({})", right),
+                    _ => {
+                        write!(f, " ")?;
+                        right.fmt_helper(f, last)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Tree {
+
+    // Variables are bound by the innermost binder with their bound id, the
+    // others are free and keep their name
+    pub fn to_de_bruijn(&self, names : &HashMap<isize, &str>) -> DeBruijn {
+        self.to_de_bruijn_helper(&mut vec![], names)
+    }
+
+    fn to_de_bruijn_helper(&self, scope : &mut Vec<isize>, names : &HashMap<isize, &str>) -> DeBruijn {
+        let name = |id : &isize| names.get(id).unwrap_or(&"MissingId").to_string();
+        match self {
+            Tree::Var(x, id) => match scope.iter().rev().position(|bound| bound == id) {
+                Some(index) => DeBruijn::Var(index),
+                None => DeBruijn::Free(name(x))
+            },
+            Tree::Abs(x, id, body) => {
+                scope.push(*id);
+                let body = body.to_de_bruijn_helper(scope, names);
+                scope.pop();
+                DeBruijn::Abs(name(x), Box::new(body))
+            },
+            Tree::App(left, right) => DeBruijn::App(
+                Box::new(left.to_de_bruijn_helper(scope, names)),
+                Box::new(right.to_de_bruijn_helper(scope, names)))
+        }
+    }
+}
+
+impl DeBruijn {
+
+    // The result has canonical names, numbered like `Tree::canonicalize_names`
+    // does. Names missing from the map get ids past the largest one in it.
+    // An index past every binder around it is a free variable named after
+    // how far past them it points, 0 for the first.
+    pub fn to_tree(&self, names : &HashMap<isize, &str>) -> Tree {
+        let mut ids : HashMap<String, isize> = names.iter().map(|(id, name)| (name.to_string(), *id)).collect();
+        let mut next_name = names.keys().max().map_or(1, |x| x + 1);
+        let mut bound_id = 0;
+        self.to_tree_helper(&mut vec![], &mut ids, &mut next_name, &mut bound_id)
+    }
+
+    fn to_tree_helper(&self,
+        scope : &mut Vec<(isize, isize)>,
+        ids : &mut HashMap<String, isize>,
+        next_name : &mut isize,
+        bound_id : &mut isize) -> Tree
+    {
+        let mut id_of = |name : String| *ids.entry(name).or_insert_with(|| {
+            *next_name += 1;
+            *next_name - 1
+        });
+        match self {
+            DeBruijn::Var(index) => match scope.len().checked_sub(index + 1) {
+                Some(position) => {
+                    let (name, id) = scope[position];
+                    Tree::Var(name, id)
+                },
+                None => Tree::Var(id_of((index - scope.len()).to_string()), 0)
+            },
+            DeBruijn::Free(name) => Tree::Var(id_of(name.clone()), 0),
+            DeBruijn::Abs(name, body) => {
+                let name = id_of(name.clone());
+                *bound_id += 1;
+                let id = *bound_id;
+                scope.push((name, id));
+                let body = body.to_tree_helper(scope, ids, next_name, bound_id);
+                scope.pop();
+                Tree::Abs(name, id, Box::new(body))
+            },
+            DeBruijn::App(left, right) => {
+                let left = left.to_tree_helper(scope, ids, next_name, bound_id);
+                let right = right.to_tree_helper(scope, ids, next_name, bound_id);
+                Tree::App(Box::new(left), Box::new(right))
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            DeBruijn::Var(_) | DeBruijn::Free(_) => 1,
+            DeBruijn::Abs(_, body) => 1 + body.size(),
+            DeBruijn::App(left, right) => 1 + left.size() + right.size()
+        }
+    }
+
+    // Normal order reduction
+    pub fn reduce(&self, limits : &Limits) -> NamelessReduction {
+        let start = limits.start();
+        let mut term = self.clone();
+        let mut steps = 0;
+        let outcome = loop {
+            if let Some(outcome) = limits.check(steps, &start).or_else(|| limits.check_size(|| term.size())) {
+                break outcome;
+            }
+            match DeBruijn::normal_step(term) {
+                Ok(next) => { term = next; steps += 1; },
+                Err(last) => { term = last; break Outcome::Normal; }
+            }
+        };
+        NamelessReduction { term, steps, outcome }
+    }
+
+    fn normal_step(term : DeBruijn) -> Result<DeBruijn, DeBruijn> {
+        match term {
+            DeBruijn::App(left, right) => match *left {
+                DeBruijn::Abs(_, body) => Ok(body.substitute(0, &right)),
+                left => match DeBruijn::normal_step(left) {
+                    Ok(left) => Ok(DeBruijn::App(Box::new(left), right)),
+                    Err(left) => match DeBruijn::normal_step(*right) {
+                        Ok(right) => Ok(DeBruijn::App(Box::new(left), Box::new(right))),
+                        Err(right) => Err(DeBruijn::App(Box::new(left), Box::new(right)))
+                    }
+                }
+            },
+            DeBruijn::Abs(name, body) => match DeBruijn::normal_step(*body) {
+                Ok(body) => Ok(DeBruijn::Abs(name, Box::new(body))),
+                Err(body) => Err(DeBruijn::Abs(name, Box::new(body)))
+            },
+            term => Err(term)
+        }
+    }
+
+    // Replaces the variable `depth` binders out with the argument, the
+    // variables bound further out lose the binder that was removed
+    fn substitute(&self, depth : usize, argument : &DeBruijn) -> DeBruijn {
+        match self {
+            DeBruijn::Var(index) if *index == depth => argument.shift(depth, 0),
+            DeBruijn::Var(index) if *index > depth => DeBruijn::Var(index - 1),
+            DeBruijn::Abs(name, body) => DeBruijn::Abs(name.clone(), Box::new(body.substitute(depth + 1, argument))),
+            DeBruijn::App(left, right) => DeBruijn::App(
+                Box::new(left.substitute(depth, argument)),
+                Box::new(right.substitute(depth, argument))),
+            term => term.clone()
+        }
+    }
+
+    // Moves the variables bound outside of the term `by` binders further out
+    fn shift(&self, by : usize, cutoff : usize) -> DeBruijn {
+        match self {
+            DeBruijn::Var(index) if *index >= cutoff => DeBruijn::Var(index + by),
+            DeBruijn::Abs(name, body) => DeBruijn::Abs(name.clone(), Box::new(body.shift(by, cutoff + 1))),
+            DeBruijn::App(left, right) => DeBruijn::App(
+                Box::new(left.shift(by, cutoff)),
+                Box::new(right.shift(by, cutoff))),
+            term => term.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use typical::{borrow_names, parse_with_prelude};
+
+    fn parse(input : &str) -> (Tree, HashMap<isize, String>) {
+        parse_with_prelude(input).expect(input)
+    }
+
+    fn var(index : usize) -> DeBruijn { DeBruijn::Var(index) }
+    fn free(name : &str) -> DeBruijn { DeBruijn::Free(name.to_string()) }
+    fn abs(body : DeBruijn) -> DeBruijn { DeBruijn::Abs("x".to_string(), Box::new(body)) }
+    fn app(left : DeBruijn, right : DeBruijn) -> DeBruijn { DeBruijn::App(Box::new(left), Box::new(right)) }
+
+    #[test]
+    fn conversion_round_trips() {
+        let corpus = [
+            "x", "\\x.x", "\\x y.x", "\\x.\\x.x", "\\x.(\\x.x) x", "\\f x.f (f x) y",
+            "(\\x.x x) (\\y.y)", "S K K", "plus two three"
+        ];
+        for input in corpus.iter() {
+            let (tree, names) = parse(input);
+            let names = borrow_names(&names);
+            let term = tree.to_de_bruijn(&names);
+            let back = term.to_tree(&names);
+            assert_eq!(back, tree, "converting {:?}", input);
+            assert_eq!(back.to_string(&names), tree.to_string(&names), "converting {:?}", input);
+            assert_eq!(back.to_de_bruijn(&names), term, "converting {:?}", input);
+        }
+    }
+
+    #[test]
+    fn reduces_like_the_tree() {
+        let corpus = [
+            ("(\\x.x) y", "y"),
+            ("K a b", "a"),
+            ("\\y.(\\x.\\y.x) y", "λ.λ.1"),
+            ("(\\x.\\y.x y) (\\z.y)", "λ.y"),
+            ("plus two three", "λ.λ.1 (1 (1 (1 (1 0))))"),
+        ];
+        for (input, expected) in corpus.iter() {
+            let (tree, names) = parse(input);
+            let names = borrow_names(&names);
+            let reduction = tree.to_de_bruijn(&names).reduce(&Limits::steps(1_000));
+            assert_eq!(reduction.outcome, Outcome::Normal, "reducing {:?}", input);
+            assert_eq!(reduction.term.to_string(), *expected, "reducing {:?}", input);
+
+            let tree_reduction = tree.reduce(::typical::Strategy::NormalOrder, &Limits::steps(1_000));
+            assert_eq!(tree_reduction.tree.to_de_bruijn(&names), reduction.term, "reducing {:?}", input);
+        }
+
+        let omega = app(abs(app(var(0), var(0))), abs(app(var(0), var(0))));
+        let reduction = omega.reduce(&Limits::steps(10));
+        assert_eq!((reduction.steps, reduction.outcome), (10, Outcome::StepLimit));
+        assert_eq!(reduction.term, omega);
+    }
+
+    #[test]
+    fn printing() {
+        let corpus = [
+            (abs(var(0)), "λ.0"),
+            (app(free("f"), abs(var(0))), "f λ.0"),
+            (app(app(free("f"), abs(var(0))), free("x")), "f (λ.0) x"),
+            (app(app(free("f"), abs(app(var(0), free("y")))), free("x")), "f (λ.0 y) x"),
+            (app(abs(var(0)), free("x")), "(λ.0) x"),
+            (app(free("f"), app(free("g"), abs(var(0)))), "f (g λ.0)"),
+            (abs(app(app(var(0), abs(var(0))), var(0))), "λ.0 (λ.0) 0"),
+            (app(free("f"), abs(app(var(0), abs(var(0))))), "f λ.0 λ.0"),
+        ];
+        for (term, expected) in corpus.iter() {
+            assert_eq!(term.to_string(), *expected);
+        }
+    }
+
+    #[test]
+    fn dangling_indices_are_free() {
+        let names = [(1, "x")].iter().cloned().collect();
+        let tree = abs(app(var(0), var(2))).to_tree(&names);
+        match tree {
+            Tree::Abs(_, _, body) => match *body {
+                Tree::App(_, right) => assert!(matches!(*right, Tree::Var(_, 0))),
+                body => panic!("unexpected body {:?}", body)
+            },
+            tree => panic!("unexpected tree {:?}", tree)
+        }
+        // Named after how far past the outermost binder they point
+        let named = [(1, "x"), (2, "1")].iter().cloned().collect();
+        assert_eq!(abs(var(2)).to_tree(&names).to_de_bruijn(&named), abs(free("1")));
+    }
+}
@@ -1,5 +1,13 @@
+mod debruijn;
 mod parser;
+mod prelude;
+mod strategy;
 mod tree;
+mod value;
 
+pub use self::debruijn::*;
 pub use self::parser::*;
+pub use self::prelude::*;
+pub use self::strategy::*;
 pub use self::tree::*;
+pub use self::value::*;
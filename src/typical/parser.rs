@@ -1,73 +1,276 @@
 use std::collections::{VecDeque, HashMap};
+use std::fmt;
+use std::mem;
 use std::str;
 
-use typical::Tree;
-use lexer::{Lexer, Token};
+use typical::{Tree, PRELUDE};
+use lexer::{Lexer, Token, Span};
+use utils::error_json;
 
-#[derive(Debug)]
-pub enum ParseError {
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParseErrorKind {
     MisplacedDot,
     UnclosedParen,
     UnopenedParen,
     EmptyExpression,
     EmptyAbstraction,
-    ParenInAbstraction,
-    NestedAbstraction
+    MissingDot,
+    MisplacedEquals,
+    MissingSemicolon,
+    UnboundInDefinition,
+    TrailingInput,
+    MisplacedNumber,
+    NumberTooLarge
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::MisplacedDot => write!(f, "dot outside of an abstraction"),
+            ParseErrorKind::UnclosedParen => write!(f, "parenthesis is never closed"),
+            ParseErrorKind::UnopenedParen => write!(f, "parenthesis was never opened"),
+            ParseErrorKind::EmptyExpression => write!(f, "expected an expression"),
+            ParseErrorKind::EmptyAbstraction => write!(f, "abstraction binds no variable"),
+            ParseErrorKind::MissingDot => write!(f, "abstraction is missing its dot"),
+            ParseErrorKind::MisplacedEquals => write!(f, "equals sign outside of a definition"),
+            ParseErrorKind::MissingSemicolon => write!(f, "definition is missing its semicolon"),
+            ParseErrorKind::UnboundInDefinition => write!(f, "name is neither bound nor defined"),
+            ParseErrorKind::TrailingInput => write!(f, "input continues after the term"),
+            ParseErrorKind::MisplacedNumber => write!(f, "number used as a binder"),
+            ParseErrorKind::NumberTooLarge => write!(f, "number is larger than {}", MAX_NUMERAL),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind : ParseErrorKind,
+    pub span : Span,
+    pub line : usize, // Both counted from 1, the column in characters
+    pub column : usize,
+    text : String // The offending line, to underline in the diagnostic
+}
+
+impl ParseError {
+    fn new(input : &[u8], kind : ParseErrorKind, span : Span) -> ParseError {
+        let line_start = input[..span.start].iter()
+            .rposition(|&x| x == b'\n')
+            .map_or(0, |i| i + 1);
+        let line_end = input[span.start..].iter()
+            .position(|&x| x == b'\n')
+            .map_or(input.len(), |i| span.start + i);
+        let line = 1 + input[..line_start].iter().filter(|&&x| x == b'\n').count();
+        let column = 1 + String::from_utf8_lossy(&input[line_start..span.start]).chars().count();
+        let text = String::from_utf8_lossy(&input[line_start..line_end])
+            .trim_end_matches('\r')
+            .to_string();
+        ParseError { kind, span, line, column, text }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self.kind {
+            ParseErrorKind::MisplacedDot => "MisplacedDot",
+            ParseErrorKind::UnclosedParen => "UnclosedParen",
+            ParseErrorKind::UnopenedParen => "UnopenedParen",
+            ParseErrorKind::EmptyExpression => "EmptyExpression",
+            ParseErrorKind::EmptyAbstraction => "EmptyAbstraction",
+            ParseErrorKind::MissingDot => "MissingDot",
+            ParseErrorKind::MisplacedEquals => "MisplacedEquals",
+            ParseErrorKind::MissingSemicolon => "MissingSemicolon",
+            ParseErrorKind::UnboundInDefinition => "UnboundInDefinition",
+            ParseErrorKind::TrailingInput => "TrailingInput",
+            ParseErrorKind::MisplacedNumber => "MisplacedNumber",
+            ParseErrorKind::NumberTooLarge => "NumberTooLarge"
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        error_json(self.kind(), &self.kind.to_string(), json!({
+            "start": self.span.start,
+            "end": self.span.end,
+            "line": self.line,
+            "column": self.column,
+            "text": self.to_string()
+        }))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        // Keep tabs so the caret lines up with the text above it
+        let padding : String = self.text.chars()
+            .take(self.column - 1)
+            .map(|x| if x == '\t' { '\t' } else { ' ' })
+            .collect();
+        // The span counts bytes, the caret one per character of it on this line
+        let start = self.text.char_indices()
+            .nth(self.column - 1)
+            .map_or(self.text.len(), |(i, _)| i);
+        let end = start + (self.span.end - self.span.start);
+        let width = self.text[start..].char_indices()
+            .take_while(|(i, _)| start + i < end)
+            .count()
+            .max(1);
+        writeln!(f, "error: {} at {}:{}", self.kind, self.line, self.column)?;
+        writeln!(f, "{}", self.text)?;
+        write!(f, "{}{}", padding, "^".repeat(width))
+    }
+}
+
+// Numerals are unary, so keep literals small enough to build and print
+pub const MAX_NUMERAL : usize = 4096;
+
+// How decimal literals are desugared
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Numerals {
+    Church, // n = λf x.f (... (f x))
+    Scott   // 0 = λs z.z and n + 1 = λs z.s n
 }
 
 pub struct Parser<'a> {
     input : &'a [u8],
     lexer :  Lexer<'a>,
-    stack : Vec<Token>,
+    stack : Vec<Span>, // Opening parentheses not yet closed
     names : HashMap<&'a [u8], isize>,
-    id : isize
+    id : isize,
+    scope : Vec<&'a [u8]>, // Binders enclosing the current position
+    definitions : HashMap<&'a [u8], Tree>,
+    defining : bool,
+    numerals : Numerals
 }
 
 impl<'a> Parser<'a> {
 
     pub fn new(input : &'a [u8], lexer : Lexer<'a>) -> Parser<'a> {
-        Parser { input, lexer, stack: vec![], names: HashMap::new(), id: 0 }
+        Parser {
+            input, lexer,
+            stack: vec![],
+            names: HashMap::new(), id: 0,
+            scope: vec![],
+            definitions: HashMap::new(),
+            defining: false,
+            numerals: Numerals::Church
+        }
+    }
+
+    pub fn set_numerals(&mut self, numerals : Numerals) {
+        self.numerals = numerals;
     }
 
     pub fn names_map(&self) -> HashMap<isize, &str> {
         let mut map = HashMap::new();
         for (key, value) in self.names.iter() {
-            map.insert(*value, str::from_utf8(*key).unwrap_or("InvalidUTF8"));
+            map.insert(*value, str::from_utf8(key).unwrap_or("InvalidUTF8"));
         }
         map
     }
 
-    fn parse_name(&mut self, start : usize, length : usize) -> Result<Tree, ParseError> {
-        if let Some(name) = self.input.get(start..(start+length)) {
-            if let Some(&id) = self.names.get(name) {
-                Ok(Tree::Var(id, id))
-            } else {
-                self.id += 1;
-                self.names.insert(name, self.id);
-                Ok(Tree::Var(self.id, self.id))
+    fn error(&self, kind : ParseErrorKind, span : Span) -> ParseError {
+        ParseError::new(self.input, kind, span)
+    }
+
+    fn name_at(&self, start : usize, length : usize) -> &'a [u8] {
+        let input = self.input;
+        // Less obvious to as why, if this fails then the lexer is fatally bugged, so just panic
+        input.get(start..(start+length)).expect("Name outside of the input.")
+    }
+
+    fn intern(&mut self, name : &'a [u8]) -> isize {
+        if let Some(&id) = self.names.get(name) {
+            id
+        } else {
+            self.id += 1;
+            self.names.insert(name, self.id);
+            self.id
+        }
+    }
+
+    fn bind_name(&mut self, start : usize, length : usize) -> Tree {
+        let id = self.intern(self.name_at(start, length));
+        Tree::Var(id, id)
+    }
+
+    fn parse_number(&mut self, start : usize, length : usize) -> Result<Tree, ParseError> {
+        let n = str::from_utf8(self.name_at(start, length)).ok()
+            .and_then(|x| x.parse::<usize>().ok())
+            .filter(|&x| x <= MAX_NUMERAL)
+            .ok_or_else(|| self.error(ParseErrorKind::NumberTooLarge, self.lexer.span()))?;
+        let tree = match self.numerals {
+            Numerals::Church => {
+                let (f, x) = (self.intern(b"f"), self.intern(b"x"));
+                let mut body = Tree::Var(x, x);
+                for _ in 0..n {
+                    body = Tree::App(Box::new(Tree::Var(f, f)), Box::new(body));
+                }
+                Tree::Abs(f, f, Box::new(Tree::Abs(x, x, Box::new(body))))
+            },
+            Numerals::Scott => {
+                let (s, z) = (self.intern(b"s"), self.intern(b"z"));
+                let mut tree = Tree::Abs(s, s, Box::new(Tree::Abs(z, z, Box::new(Tree::Var(z, z)))));
+                for _ in 0..n {
+                    let body = Tree::App(Box::new(Tree::Var(s, s)), Box::new(tree));
+                    tree = Tree::Abs(s, s, Box::new(Tree::Abs(z, z, Box::new(body))));
+                }
+                tree
             }
+        };
+        Ok(tree)
+    }
+
+    // Names that no enclosing binder captures refer to definitions when there is one,
+    // definitions themselves must be closed so that expanding them never captures
+    fn parse_name(&mut self, start : usize, length : usize) -> Result<Tree, ParseError> {
+        let name = self.name_at(start, length);
+        if self.scope.contains(&name) {
+            Ok(self.bind_name(start, length))
+        } else if let Some(tree) = self.definitions.get(name) {
+            Ok(tree.clone())
+        } else if self.defining {
+            Err(self.error(ParseErrorKind::UnboundInDefinition, self.lexer.span()))
         } else {
-            // Less obvious to as why, if we reach here then the lexer is fatally bugged, so just panic
-            unreachable!();
+            Ok(self.bind_name(start, length))
         }
     }
 
-    fn parse_abstraction(&mut self) -> Result<Tree, ParseError> {
+    // Binders may be grouped in parentheses and repeat the lambda, so that
+    // `\x y.M`, `\x \y.M` and `\x (y).M` all mean `\x.\y.M`
+    fn parse_abstraction(&mut self, lambda : Span) -> Result<Tree, ParseError> {
         let mut names = vec![];
+        let mut parens = vec![];
         loop {
-            match self.lexer.next() {
-                Some(Token::Name(start, length)) => names.push(self.parse_name(start, length)?),
-                Some(Token::Dot) if names.is_empty() => return Err(ParseError::EmptyAbstraction),
-                Some(Token::Dot) => { break; },
-                Some(Token::OpenParen) | Some(Token::CloseParen) => return Err(ParseError::ParenInAbstraction),
-                Some(Token::Lambda) => return Err(ParseError::NestedAbstraction),
-                None => return Err(ParseError::EmptyAbstraction)
+            let token = self.lexer.next();
+            let span = self.lexer.span();
+            let binders = Span { start: lambda.start, end: span.end };
+            match token {
+                Some(Token::Name(start, length)) => {
+                    names.push(self.bind_name(start, length));
+                    self.scope.push(self.name_at(start, length));
+                },
+                Some(Token::Lambda) => { },
+                Some(Token::Number(_, _)) => return Err(self.error(ParseErrorKind::MisplacedNumber, span)),
+                Some(Token::Equals) => return Err(self.error(ParseErrorKind::MisplacedEquals, span)),
+                Some(Token::OpenParen) => parens.push(span),
+                Some(Token::CloseParen) => if parens.pop().is_none() {
+                    return Err(self.error(ParseErrorKind::UnopenedParen, span))
+                },
+                Some(Token::Dot) => if let Some(&open) = parens.last() {
+                    return Err(self.error(ParseErrorKind::UnclosedParen, open))
+                } else if names.is_empty() {
+                    return Err(self.error(ParseErrorKind::EmptyAbstraction, binders))
+                } else {
+                    break
+                },
+                None | Some(Token::Semicolon) if names.is_empty() =>
+                    return Err(self.error(ParseErrorKind::EmptyAbstraction, binders)),
+                None | Some(Token::Semicolon) => return Err(self.error(ParseErrorKind::MissingDot, binders))
             }
         }
         let body = self.parse_application()?;
+        let length = self.scope.len() - names.len();
+        self.scope.truncate(length);
 
         match names.len() {
-            0 => Err(ParseError::EmptyAbstraction),
+            0 => Err(self.error(ParseErrorKind::EmptyAbstraction, lambda)),
             _ => {
                 let id = match names.pop().expect("Impossible.") {
                     Tree::Var(id, _) => id,
@@ -84,39 +287,55 @@ impl<'a> Parser<'a> {
 
     fn parse_application(&mut self) -> Result<Tree, ParseError> {
         let mut trees = VecDeque::new();
+        // Where the expression ended, to point at when it turns out to be empty
+        let end;
         loop {
-            let part = match self.lexer.next() {
+            let token = self.lexer.next();
+            let span = self.lexer.span();
+            let part = match token {
                 Some(Token::OpenParen) => { 
-                    self.stack.push(Token::OpenParen);
+                    self.stack.push(span);
                     let result = self.parse_application()?;
                     match self.lexer.next() {
                         Some(Token::CloseParen) => {
                             self.stack.pop();
                             result
                         },
-                        _ => return Err(ParseError::UnclosedParen)
+                        _ => return Err(self.error(ParseErrorKind::UnclosedParen, span))
                     }
                 },
-                Some(Token::Lambda) => self.parse_abstraction()?,
+                Some(Token::Lambda) => self.parse_abstraction(span)?,
                 Some(Token::Name(start, length)) => self.parse_name(start, length)?,
+                Some(Token::Number(start, length)) => self.parse_number(start, length)?,
                 Some(Token::CloseParen) => {
                     if !self.stack.is_empty() {
                         self.lexer.backtrack(1);
+                        end = span;
                         break 
                     } else { 
-                        return Err(ParseError::UnopenedParen)
+                        return Err(self.error(ParseErrorKind::UnopenedParen, span))
                     }
                 },
-                Some(Token::Dot) => return Err(ParseError::MisplacedDot),
-                None => {
-                    if self.stack.is_empty() { break } else { return Err(ParseError::UnclosedParen) }
+                Some(Token::Dot) => return Err(self.error(ParseErrorKind::MisplacedDot, span)),
+                Some(Token::Equals) => return Err(self.error(ParseErrorKind::MisplacedEquals, span)),
+                Some(Token::Semicolon) if self.stack.is_empty() => {
+                    self.lexer.backtrack(1);
+                    end = span;
+                    break
+                },
+                Some(Token::Semicolon) | None => {
+                    if let Some(&open) = self.stack.last() {
+                        return Err(self.error(ParseErrorKind::UnclosedParen, open))
+                    }
+                    end = span;
+                    break
                 }
             };
             trees.push_back(part);
         }
 
         match trees.len() {
-            0 => Err(ParseError::EmptyExpression),
+            0 => Err(self.error(ParseErrorKind::EmptyExpression, end)),
             1 => Ok(trees.pop_front().expect("Impossible.")),
             _ => {
                 let mut accumulator = Tree::App(
@@ -132,8 +351,222 @@ impl<'a> Parser<'a> {
         }
     }
 
-    #[inline(always)]
+    // Definitions are `name = term;`, each may use the ones before it
+    fn parse_definitions(&mut self) -> Result<(), ParseError> {
+        loop {
+            match (self.lexer.next(), self.lexer.next()) {
+                (Some(Token::Name(start, length)), Some(Token::Equals)) => {
+                    self.defining = true;
+                    let tree = self.parse_application();
+                    self.defining = false;
+                    let tree = tree?;
+                    match self.lexer.next() {
+                        Some(Token::Semicolon) => {
+                            let name = self.name_at(start, length);
+                            self.definitions.insert(name, tree);
+                        },
+                        _ => return Err(self.error(ParseErrorKind::MissingSemicolon, self.lexer.span()))
+                    }
+                },
+                (None, _) => return Ok(()),
+                (_, None) => { self.lexer.backtrack(1); return Ok(()) },
+                (_, Some(_)) => { self.lexer.backtrack(2); return Ok(()) }
+            }
+        }
+    }
+
+    // Reads definitions from another source, for instance the prelude, and
+    // makes them available to everything parsed afterwards
+    pub fn define(&mut self, source : &'a str) -> Result<(), ParseError> {
+        let input = mem::replace(&mut self.input, source.as_bytes());
+        let lexer = mem::replace(&mut self.lexer, Lexer::new(source.as_bytes()));
+        let result = self.parse_definitions().and_then(|_| match self.lexer.next() {
+            Some(_) => Err(self.error(ParseErrorKind::TrailingInput, self.lexer.span())),
+            None => Ok(())
+        });
+        self.input = input;
+        self.lexer = lexer;
+        result
+    }
+
     pub fn parse(&mut self) -> Result<Tree, ParseError> {
-        self.parse_application()
+        self.parse_definitions()?;
+        let tree = self.parse_application()?;
+        // A final semicolon after the term is allowed, anything else is not
+        let mut token = self.lexer.next();
+        if let Some(Token::Semicolon) = token {
+            token = self.lexer.next();
+        }
+        match token {
+            Some(_) => Err(self.error(ParseErrorKind::TrailingInput, self.lexer.span())),
+            None => Ok(tree)
+        }
+    }
+}
+
+// Parses a term with the prelude defined and canonical names, the way the
+// interface and the command line load one. The names map owns its names,
+// `borrow_names` gives the form printing takes.
+pub fn parse_with_prelude(source : &str) -> Result<(Tree, HashMap<isize, String>), ParseError> {
+    let input = source.as_bytes();
+    let mut parser = Parser::new(input, Lexer::new(input));
+    parser.define(PRELUDE).expect("The prelude is well formed.");
+    let mut tree = parser.parse()?;
+    tree.canonicalize_names();
+    let names = parser.names_map().into_iter().map(|(id, name)| (id, name.to_string())).collect();
+    Ok((tree, names))
+}
+
+pub fn borrow_names(names : &HashMap<isize, String>) -> HashMap<isize, &str> {
+    names.iter().map(|(id, name)| (*id, name.as_str())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input : &str) -> Result<String, ParseError> {
+        let mut parser = Parser::new(input.as_bytes(), Lexer::new(input.as_bytes()));
+        let mut tree = parser.parse()?;
+        tree.canonicalize_names();
+        Ok(tree.to_string(&parser.names_map()))
+    }
+
+    fn print_with_prelude(input : &str) -> Result<String, ParseError> {
+        let (tree, names) = parse_with_prelude(input)?;
+        Ok(tree.to_string(&borrow_names(&names)))
+    }
+
+    #[test]
+    fn equivalent_forms() {
+        let corpus : &[(&str, &[&str])] = &[
+            ("x", &["x", "(x)", "((x))", " x "]),
+            ("λx.x", &["\\x.x", "λx.x", "(\\x.x)", "\\x.(x)", "\\ x . x", "\\(x).x"]),
+            ("λx y z.x", &[
+                "\\x y z.x", "\\x.\\y.\\z.x", "λx.λy.λz.x", "λx y.λz.x", "\\x \\y \\z.x",
+                "\\x (y) z.x", "\\x (y z).x", "(\\x.(\\y.(\\z.x)))", "λxλyλz.x"]),
+            ("(f x) y", &["f x y", "(f x) y", "((f x) y)", "(f) (x) (y)"]),
+            ("f (x y)", &["f (x y)", "(f (x y))"]),
+            ("f λx.x y", &["f \\x.x y", "f (\\x.x y)", "f λx.(x y)"]),
+            ("(λx.x) y", &["(\\x.x) y", "(λx.x) y", "((λx.x)) (y)"]),
+            ("λx.x λy.y", &["\\x.x \\y.y", "\\x.(x (\\y.y))", "λx.x λy.y"]),
+            ("λf.(λx.f (x x)) λx.f (x x)", &[
+                "\\f.(\\x.f (x x)) (\\x.f (x x))", "λf.(λx.f (x x)) λx.f (x x)"]),
+            ("λx x.x", &["\\x.\\x.x", "\\x x.x"]),
+        ];
+        for (expected, inputs) in corpus {
+            for input in inputs.iter() {
+                assert_eq!(parse(input).as_ref().map(|x| x.as_str()), Ok(*expected), "parsing {:?}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn rejected_forms() {
+        let corpus : &[(&str, ParseErrorKind)] = &[
+            ("", ParseErrorKind::EmptyExpression),
+            ("()", ParseErrorKind::EmptyExpression),
+            ("\\x.", ParseErrorKind::EmptyExpression),
+            ("(x", ParseErrorKind::UnclosedParen),
+            ("\\x (y.x", ParseErrorKind::UnclosedParen),
+            ("x)", ParseErrorKind::UnopenedParen),
+            ("\\x y).x", ParseErrorKind::UnopenedParen),
+            ("x.y", ParseErrorKind::MisplacedDot),
+            ("\\.x", ParseErrorKind::EmptyAbstraction),
+            ("\\", ParseErrorKind::EmptyAbstraction),
+            ("\\x y", ParseErrorKind::MissingDot),
+            ("\\x;", ParseErrorKind::MissingDot),
+            ("f x = y", ParseErrorKind::MisplacedEquals),
+            ("\\x = y.x", ParseErrorKind::MisplacedEquals),
+            ("a = \\x.x", ParseErrorKind::MissingSemicolon),
+            ("a = (\\x.x; a", ParseErrorKind::UnclosedParen),
+            ("a = b; a", ParseErrorKind::UnboundInDefinition),
+            ("a = \\x.a; a", ParseErrorKind::UnboundInDefinition),
+            ("x; y", ParseErrorKind::TrailingInput),
+            ("\\1.x", ParseErrorKind::MisplacedNumber),
+            ("99999", ParseErrorKind::NumberTooLarge),
+            ("99999999999999999999999", ParseErrorKind::NumberTooLarge),
+        ];
+        for (input, error) in corpus {
+            assert_eq!(parse(input).err().map(|e| e.kind), Some(*error), "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn definitions() {
+        let corpus = [
+            ("id = \\x.x; id y", "(λx.x) y"),
+            ("id = \\x.x; id y;", "(λx.x) y"),
+            ("a = \\x.x; b = a a; b", "(λx.x) λx.x"),
+            ("id = \\x.x; \\id.id", "λid.id"),
+            ("id = \\x.x; \\y.id", "λy x.x"),
+            ("id = \\x.x; \\x.id x", "λx.(λx.x) x"),
+            ("id = \\x.x;\nconst = \\x y.x;\nconst id", "(λx y.x) λx.x"),
+        ];
+        for (input, expected) in corpus.iter() {
+            assert_eq!(parse(input).as_ref().map(|x| x.as_str()), Ok(*expected), "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn numerals() {
+        let corpus = [
+            ("0", Numerals::Church, "λf x.x"),
+            ("3", Numerals::Church, "λf x.f (f (f x))"),
+            ("f 1 x1", Numerals::Church, "(f λf x.f x) x1"),
+            ("\\f.f 1", Numerals::Church, "λf.f λf x.f x"),
+            ("0", Numerals::Scott, "λs z.z"),
+            ("2", Numerals::Scott, "λs z.s λs z.s λs z.z"),
+        ];
+        for (input, numerals, expected) in corpus.iter() {
+            let mut parser = Parser::new(input.as_bytes(), Lexer::new(input.as_bytes()));
+            parser.set_numerals(*numerals);
+            let mut tree = parser.parse().expect(input);
+            tree.canonicalize_names();
+            assert_eq!(tree.to_string(&parser.names_map()), *expected, "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn prelude() {
+        let corpus = [
+            ("K I", "(λx y.x) λx.x"),
+            ("fst (pair a b)", "(λp.p λt f.t) (((λa b p.(p a) b) a) b)"),
+            ("\\K.K I", "λK.K λx.x"),
+            ("I = \\y.y; I", "λy.y"),
+        ];
+        for (input, expected) in corpus.iter() {
+            assert_eq!(print_with_prelude(input).as_ref().map(|x| x.as_str()), Ok(*expected),
+                "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn error_spans() {
+        let corpus : &[(&str, Span, (usize, usize))] = &[
+            ("", Span { start: 0, end: 0 }, (1, 1)),
+            ("f (x", Span { start: 2, end: 3 }, (1, 3)),
+            ("f x)", Span { start: 3, end: 4 }, (1, 4)),
+            ("λx.x.y", Span { start: 5, end: 6 }, (1, 5)),
+            ("f (\\x.x)\n  (g x", Span { start: 11, end: 12 }, (2, 3)),
+            ("f (\\x.x)\n  (g ())", Span { start: 15, end: 16 }, (2, 7)),
+            ("\\x y", Span { start: 0, end: 4 }, (1, 1)),
+        ];
+        for (input, span, (line, column)) in corpus {
+            let error = parse(input).expect_err(input);
+            assert_eq!(error.span, *span, "parsing {:?}", input);
+            assert_eq!((error.line, error.column), (*line, *column), "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn error_display() {
+        let error = parse("λx.(x\n\ty.z)").expect_err("parsing");
+        assert_eq!(error.to_string(), "error: dot outside of an abstraction at 2:3\n\ty.z)\n\t ^");
+        let error = parse("λx.(x y").expect_err("parsing");
+        assert_eq!(error.to_string(), "error: parenthesis is never closed at 1:4\nλx.(x y\n   ^");
+        // One caret for each character of the span, not for each byte
+        let error = parse("f = ééé; f").expect_err("parsing");
+        assert_eq!(error.to_string(), "error: name is neither bound nor defined at 1:5\nf = ééé; f\n    ^^^");
     }
 }
@@ -0,0 +1,40 @@
+// Definitions available to every term loaded in the interface, see `Parser::define`
+pub const PRELUDE : &str = r"
+I = \x.x;
+K = \x y.x;
+S = \x y z.x z (y z);
+B = \f g x.f (g x);
+C = \f x y.f y x;
+W = \f x.f x x;
+Y = \f.(\x.f (x x)) (\x.f (x x));
+
+true = \t f.t;
+false = \t f.f;
+if = \b t f.b t f;
+not = \b.b false true;
+and = \a b.a b false;
+or = \a b.a true b;
+
+pair = \a b p.p a b;
+fst = \p.p true;
+snd = \p.p false;
+
+zero = \f x.x;
+one = \f x.f x;
+two = \f x.f (f x);
+three = \f x.f (f (f x));
+succ = \n f x.f (n f x);
+pred = \n f x.n (\g h.h (g f)) (\u.x) (\u.u);
+plus = \m n f x.m f (n f x);
+mult = \m n f.m (n f);
+pow = \b e.e b;
+minus = \m n.n pred m;
+iszero = \n.n (\x.false) true;
+leq = \m n.iszero (minus m n);
+eq = \m n.and (leq m n) (leq n m);
+
+nil = \c n.n;
+cons = \h t c n.c h (t c n);
+isnil = \l.l (\h t.false) true;
+head = \l.l (\h t.h) nil;
+";
@@ -0,0 +1,726 @@
+use std::mem;
+use std::rc::Rc;
+use std::time::Instant;
+
+use typical::{Branch, Tree};
+use limits::{Limits, Outcome};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    NormalOrder,      // Leftmost outermost redex first, to normal form
+    ApplicativeOrder, // Leftmost innermost redex first, to normal form
+    CallByName,       // Leftmost outermost redex outside of abstractions, to weak head normal form
+    CallByValue,      // Arguments are reduced before they are passed, to weak normal form
+    CallByNeed,       // Call by name where every argument is reduced at most once
+    Parallel          // Every redex of the term at once (Gross-Knuth), to normal form
+}
+
+// Which reductions are allowed besides beta
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rules {
+    Beta,
+    BetaEta // λx.M x becomes M when x is not free in M
+}
+
+// A term of a trace and the paths to the redexes contracted in it, a
+// parallel step contracts all of them and the others only one
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    pub tree : Tree,
+    pub redexes : Vec<Vec<Branch>>
+}
+
+// Yields a step for every term of a reduction but the last, which is left in
+// `tree` along with the outcome once the iterator is done
+pub struct Trace {
+    pub tree : Tree,
+    strategy : Strategy,
+    limits : Limits,
+    start : Option<Instant>,
+    fresh : isize,
+    steps : usize,
+    outcome : Option<Outcome>
+}
+
+impl Trace {
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+}
+
+impl Iterator for Trace {
+    type Item = TraceStep;
+
+    fn next(&mut self) -> Option<TraceStep> {
+        if self.outcome.is_some() {
+            return None;
+        }
+        let tree = &self.tree;
+        if let Some(outcome) = self.limits.check(self.steps, &self.start).or_else(|| self.limits.check_size(|| tree.size())) {
+            self.outcome = Some(outcome);
+            return None;
+        }
+        let tree = self.tree.clone();
+        let mut path = vec![];
+        let redexes = if self.strategy == Strategy::Parallel { tree.redexes() } else { vec![] };
+        let current = mem::replace(&mut self.tree, Tree::Var(0, 0));
+        match Tree::step(current, self.strategy, &mut self.fresh, &mut path) {
+            Ok((next, n)) => {
+                self.tree = next;
+                self.steps += n;
+                let redexes = if self.strategy == Strategy::Parallel { redexes } else { vec![path] };
+                Some(TraceStep { tree, redexes })
+            },
+            Err(last) => {
+                self.tree = last;
+                self.outcome = Some(Outcome::Normal);
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Reduction {
+    pub tree : Tree,
+    pub steps : usize, // Beta reductions performed, shared ones are counted once
+    pub eta_steps : usize,
+    pub outcome : Outcome
+}
+
+impl Tree {
+
+    // Like `reduce`, eta redexes are contracted where the strategy looks for
+    // redexes, so the weak strategies only consider the root. Contracting one
+    // can make beta redexes reachable, so both take turns until neither fires.
+    pub fn reduce_with(&self, strategy : Strategy, rules : Rules, limits : &Limits) -> Reduction {
+        let start = limits.start();
+        let mut reduction = self.reduce(strategy, limits);
+        if rules == Rules::Beta {
+            return reduction;
+        }
+        let weak = matches!(strategy, Strategy::CallByName | Strategy::CallByValue | Strategy::CallByNeed);
+        while reduction.outcome == Outcome::Normal {
+            let (tree, eta_steps) = if weak {
+                match Tree::eta_contract(reduction.tree) {
+                    Ok(tree) => (tree, 1),
+                    Err(tree) => (tree, 0)
+                }
+            } else {
+                Tree::eta_normalize(reduction.tree)
+            };
+            reduction.tree = tree;
+            if eta_steps == 0 { break; }
+            reduction.eta_steps += eta_steps;
+
+            let next = reduction.tree.reduce(strategy, &limits.remaining(reduction.steps, &start));
+            reduction.tree = next.tree;
+            reduction.steps += next.steps;
+            reduction.outcome = next.outcome;
+        }
+        reduction
+    }
+
+    // Whether the variable bound with `id` occurs in the term
+    fn occurs(&self, id : isize) -> bool {
+        match self {
+            Tree::Var(_, bound_id) => *bound_id == id,
+            Tree::Abs(_, bound_id, _) if *bound_id == id => false,
+            Tree::Abs(_, _, body) => body.occurs(id),
+            Tree::App(left, right) => left.occurs(id) || right.occurs(id)
+        }
+    }
+
+    // Contracts an eta redex at the root, the term is given back when it is not one
+    fn eta_contract(tree : Tree) -> Result<Tree, Tree> {
+        match tree {
+            Tree::Abs(x, id, body) => match *body {
+                Tree::App(function, argument) => match *argument {
+                    Tree::Var(_, bound_id) if bound_id == id && !function.occurs(id) => Ok(*function),
+                    argument => Err(Tree::Abs(x, id, Box::new(Tree::App(function, Box::new(argument)))))
+                },
+                body => Err(Tree::Abs(x, id, Box::new(body)))
+            },
+            tree => Err(tree)
+        }
+    }
+
+    // Contracts eta redexes from the leaves up, so the ones a contraction
+    // creates above it are found as well
+    fn eta_normalize(tree : Tree) -> (Tree, usize) {
+        let (tree, n) = match tree {
+            Tree::Abs(x, id, body) => {
+                let (body, n) = Tree::eta_normalize(*body);
+                (Tree::Abs(x, id, Box::new(body)), n)
+            },
+            Tree::App(left, right) => {
+                let (left, n) = Tree::eta_normalize(*left);
+                let (right, m) = Tree::eta_normalize(*right);
+                (Tree::App(Box::new(left), Box::new(right)), n + m)
+            },
+            tree => (tree, 0)
+        };
+        match Tree::eta_contract(tree) {
+            Ok(tree) => (tree, n + 1),
+            Err(tree) => (tree, n)
+        }
+    }
+
+    // Reduces a term with canonical names, when a limit is hit the term
+    // reached so far is returned. A parallel step counts every redex it
+    // contracts, so it can go past the step limit by one step. Call by need
+    // shares its terms instead of copying them, its size is the number of
+    // arguments it holds.
+    pub fn reduce(&self, strategy : Strategy, limits : &Limits) -> Reduction {
+        if strategy == Strategy::CallByNeed {
+            return Machine::new(self).run(limits);
+        }
+
+        // Every binder gets an id of its own and keeps it, see `substitute`
+        let mut fresh = self.max_bound_id() + 1;
+        let start = limits.start();
+        let mut tree = Tree::rename(self, &mut vec![], &mut fresh);
+        let mut steps = 0;
+        let mut path = vec![];
+        let outcome = loop {
+            if let Some(outcome) = limits.check(steps, &start).or_else(|| limits.check_size(|| tree.size())) {
+                break outcome;
+            }
+            path.clear();
+            match Tree::step(tree, strategy, &mut fresh, &mut path) {
+                Ok((next, n)) => { tree = next; steps += n; },
+                Err(last) => { tree = last; break Outcome::Normal; }
+            }
+        };
+        Reduction { tree, steps, eta_steps: 0, outcome }
+    }
+
+    // Reduces like `reduce`, one step at a time. Call by need has no steps of
+    // its own on terms, it is traced as call by name, which contracts the same
+    // redexes without sharing them.
+    pub fn trace(&self, strategy : Strategy, limits : &Limits) -> Trace {
+        let mut fresh = self.max_bound_id() + 1;
+        let tree = Tree::rename(self, &mut vec![], &mut fresh);
+        Trace {
+            tree,
+            strategy: if strategy == Strategy::CallByNeed { Strategy::CallByName } else { strategy },
+            limits: *limits,
+            start: limits.start(),
+            fresh,
+            steps: 0,
+            outcome: None
+        }
+    }
+
+    // One step of a strategy other than call by need, with the number of
+    // redexes it contracted
+    fn step(tree : Tree, strategy : Strategy, fresh : &mut isize, path : &mut Vec<Branch>) -> Result<(Tree, usize), Tree> {
+        match strategy {
+            Strategy::NormalOrder => Tree::normal_step(tree, fresh, path).map(|x| (x, 1)),
+            Strategy::ApplicativeOrder => Tree::applicative_step(tree, fresh, path).map(|x| (x, 1)),
+            Strategy::CallByName => Tree::head_step(tree, fresh, path).map(|x| (x, 1)),
+            Strategy::CallByValue => Tree::value_step(tree, fresh, path).map(|x| (x, 1)),
+            Strategy::Parallel => match Tree::develop(tree, fresh) {
+                (x, 0) => Err(x),
+                (x, n) => Ok((x, n))
+            },
+            Strategy::CallByNeed => unreachable!()
+        }
+    }
+
+    fn max_bound_id(&self) -> isize {
+        match self {
+            Tree::Var(_, id) => *id,
+            Tree::Abs(_, id, body) => std::cmp::max(*id, body.max_bound_id()),
+            Tree::App(left, right) => std::cmp::max(left.max_bound_id(), right.max_bound_id())
+        }
+    }
+
+    // A copy of the tree where every binder has a fresh id, variables follow
+    // the innermost binder with their id and free ones are left alone
+    fn rename(tree : &Tree, scope : &mut Vec<(isize, isize)>, fresh : &mut isize) -> Tree {
+        match tree {
+            Tree::Var(x, id) => {
+                let renamed = scope.iter().rev()
+                    .find(|(old, _)| old == id)
+                    .map_or(*id, |(_, new)| *new);
+                Tree::Var(*x, renamed)
+            },
+            Tree::Abs(x, id, body) => {
+                let renamed = *fresh;
+                *fresh += 1;
+                scope.push((*id, renamed));
+                let body = Tree::rename(body, scope, fresh);
+                scope.pop();
+                Tree::Abs(*x, renamed, Box::new(body))
+            },
+            Tree::App(left, right) => Tree::App(
+                Box::new(Tree::rename(left, scope, fresh)),
+                Box::new(Tree::rename(right, scope, fresh)))
+        }
+    }
+
+    // Every occurrence receives a copy of the argument with fresh binders, so
+    // no two binders of the term ever share an id. The free variables of the
+    // argument then can not be captured by a binder of the body, since their
+    // binders lie outside of it.
+    fn substitute(tree : Tree, argument : &Tree, id : isize, fresh : &mut isize) -> Tree {
+        match tree {
+            Tree::Var(_, bound_id) if id == bound_id => Tree::rename(argument, &mut vec![], fresh),
+            Tree::Abs(x, y, expr) if y == id => Tree::Abs(x, y, expr),
+            Tree::Abs(x, y, expr) => Tree::Abs(x, y, Box::new(Tree::substitute(*expr, argument, id, fresh))),
+            Tree::App(left, right) => {
+                let left = Tree::substitute(*left, argument, id, fresh);
+                let right = Tree::substitute(*right, argument, id, fresh);
+                Tree::App(Box::new(left), Box::new(right))
+            },
+            tree => tree
+        }
+    }
+
+    // Contracts a redex at the root, the term is given back when it is not one
+    fn contract(tree : Tree, fresh : &mut isize) -> Result<Tree, Tree> {
+        match tree {
+            Tree::App(left, right) => match *left {
+                Tree::Abs(_, id, body) => Ok(Tree::substitute(*body, &right, id, fresh)),
+                left => Err(Tree::App(Box::new(left), right))
+            },
+            tree => Err(tree)
+        }
+    }
+
+    // Runs a step on a subterm with the branch to it on the path, the path is
+    // only kept when the step finds a redex
+    fn within<F>(path : &mut Vec<Branch>, branch : Branch, step : F) -> Result<Tree, Tree>
+        where F : FnOnce(&mut Vec<Branch>) -> Result<Tree, Tree>
+    {
+        path.push(branch);
+        let result = step(path);
+        if result.is_err() { path.pop(); }
+        result
+    }
+
+    // The step functions contract one redex and give back the term unchanged
+    // when the strategy finds none. The path to the redex is added to `path`.
+    fn normal_step(tree : Tree, fresh : &mut isize, path : &mut Vec<Branch>) -> Result<Tree, Tree> {
+        match Tree::contract(tree, fresh) {
+            Ok(tree) => Ok(tree),
+            Err(Tree::App(left, right)) => match Tree::within(path, Branch::Function, |path| Tree::normal_step(*left, fresh, path)) {
+                Ok(left) => Ok(Tree::App(Box::new(left), right)),
+                Err(left) => match Tree::within(path, Branch::Argument, |path| Tree::normal_step(*right, fresh, path)) {
+                    Ok(right) => Ok(Tree::App(Box::new(left), Box::new(right))),
+                    Err(right) => Err(Tree::App(Box::new(left), Box::new(right)))
+                }
+            },
+            Err(Tree::Abs(x, y, body)) => Tree::within(path, Branch::Body, |path| Tree::normal_step(*body, fresh, path))
+                .map(|body| Tree::Abs(x, y, Box::new(body)))
+                .map_err(|body| Tree::Abs(x, y, Box::new(body))),
+            Err(tree) => Err(tree)
+        }
+    }
+
+    fn applicative_step(tree : Tree, fresh : &mut isize, path : &mut Vec<Branch>) -> Result<Tree, Tree> {
+        match tree {
+            Tree::App(left, right) => match Tree::within(path, Branch::Function, |path| Tree::applicative_step(*left, fresh, path)) {
+                Ok(left) => Ok(Tree::App(Box::new(left), right)),
+                Err(left) => match Tree::within(path, Branch::Argument, |path| Tree::applicative_step(*right, fresh, path)) {
+                    Ok(right) => Ok(Tree::App(Box::new(left), Box::new(right))),
+                    Err(right) => Tree::contract(Tree::App(Box::new(left), Box::new(right)), fresh)
+                }
+            },
+            Tree::Abs(x, y, body) => Tree::within(path, Branch::Body, |path| Tree::applicative_step(*body, fresh, path))
+                .map(|body| Tree::Abs(x, y, Box::new(body)))
+                .map_err(|body| Tree::Abs(x, y, Box::new(body))),
+            tree => Err(tree)
+        }
+    }
+
+    fn head_step(tree : Tree, fresh : &mut isize, path : &mut Vec<Branch>) -> Result<Tree, Tree> {
+        match Tree::contract(tree, fresh) {
+            Ok(tree) => Ok(tree),
+            Err(Tree::App(left, right)) => match Tree::within(path, Branch::Function, |path| Tree::head_step(*left, fresh, path)) {
+                Ok(left) => Ok(Tree::App(Box::new(left), right)),
+                Err(left) => Err(Tree::App(Box::new(left), right))
+            },
+            Err(tree) => Err(tree)
+        }
+    }
+
+    fn value_step(tree : Tree, fresh : &mut isize, path : &mut Vec<Branch>) -> Result<Tree, Tree> {
+        match tree {
+            Tree::App(left, right) => match Tree::within(path, Branch::Function, |path| Tree::value_step(*left, fresh, path)) {
+                Ok(left) => Ok(Tree::App(Box::new(left), right)),
+                Err(left) => match Tree::within(path, Branch::Argument, |path| Tree::value_step(*right, fresh, path)) {
+                    Ok(right) => Ok(Tree::App(Box::new(left), Box::new(right))),
+                    Err(right) => Tree::contract(Tree::App(Box::new(left), Box::new(right)), fresh)
+                }
+            },
+            tree => Err(tree)
+        }
+    }
+
+    // The paths to every redex of the term, outermost first
+    pub fn redexes(&self) -> Vec<Vec<Branch>> {
+        let mut redexes = vec![];
+        self.redexes_helper(&mut vec![], &mut redexes);
+        redexes
+    }
+
+    fn redexes_helper(&self, path : &mut Vec<Branch>, redexes : &mut Vec<Vec<Branch>>) {
+        match self {
+            Tree::App(left, right) => {
+                if let Tree::Abs(_, _, _) = **left {
+                    redexes.push(path.clone());
+                }
+                path.push(Branch::Function);
+                left.redexes_helper(path, redexes);
+                path.pop();
+                path.push(Branch::Argument);
+                right.redexes_helper(path, redexes);
+                path.pop();
+            },
+            Tree::Abs(_, _, body) => {
+                path.push(Branch::Body);
+                body.redexes_helper(path, redexes);
+                path.pop();
+            },
+            Tree::Var(_, _) => ()
+        }
+    }
+
+    // Contracts every redex of the term and the residuals they leave in each
+    // other, returning the number of redexes of the original term
+    fn develop(tree : Tree, fresh : &mut isize) -> (Tree, usize) {
+        match tree {
+            Tree::App(left, right) => {
+                let (right, m) = Tree::develop(*right, fresh);
+                match *left {
+                    Tree::Abs(_, id, body) => {
+                        let (body, n) = Tree::develop(*body, fresh);
+                        (Tree::substitute(body, &right, id, fresh), n + m + 1)
+                    },
+                    left => {
+                        let (left, n) = Tree::develop(left, fresh);
+                        (Tree::App(Box::new(left), Box::new(right)), n + m)
+                    }
+                }
+            },
+            Tree::Abs(x, y, body) => {
+                let (body, n) = Tree::develop(*body, fresh);
+                (Tree::Abs(x, y, Box::new(body)), n)
+            },
+            tree => (tree, 0)
+        }
+    }
+}
+
+// Bindings of bound ids to heap addresses
+enum Env {
+    Empty,
+    Bind(isize, usize, Rc<Env>)
+}
+
+impl Env {
+    fn find(&self, id : isize) -> Option<usize> {
+        let mut current = self;
+        while let Env::Bind(bound_id, address, next) = current {
+            if *bound_id == id { return Some(*address); }
+            current = next;
+        }
+        None
+    }
+}
+
+#[derive(Clone)]
+enum Control<'a> {
+    Closure(&'a Tree, Rc<Env>),
+    Neutral(&'a Tree, Vec<usize>) // A free variable applied to arguments
+}
+
+enum Cell<'a> {
+    Thunk(&'a Tree, Rc<Env>),
+    Evaluating(&'a Tree, Rc<Env>),
+    Value(Control<'a>)
+}
+
+enum Frame {
+    Argument(usize),
+    Update(usize)
+}
+
+// A lazy Krivine machine, arguments live on a heap and are overwritten with
+// their weak head normal form the first time they are needed. Terms are never
+// copied, so there are no substitutions and no captures.
+struct Machine<'a> {
+    heap : Vec<Cell<'a>>,
+    stack : Vec<Frame>,
+    control : Control<'a>
+}
+
+impl<'a> Machine<'a> {
+    fn new(tree : &'a Tree) -> Machine<'a> {
+        Machine {
+            heap: vec![],
+            stack: vec![],
+            control: Control::Closure(tree, Rc::new(Env::Empty))
+        }
+    }
+
+    fn run(mut self, limits : &Limits) -> Reduction {
+        let start = limits.start();
+        let mut steps = 0;
+        let outcome = loop {
+            if let Some(outcome) = limits.check(steps, &start).or_else(|| limits.check_size(|| self.heap.len())) {
+                break outcome;
+            }
+            match self.control.clone() {
+                Control::Closure(tree, env) => match tree {
+                    Tree::App(left, right) => {
+                        self.heap.push(Cell::Thunk(right, env.clone()));
+                        self.stack.push(Frame::Argument(self.heap.len() - 1));
+                        self.control = Control::Closure(left, env);
+                    },
+                    Tree::Var(_, id) => match env.find(*id) {
+                        Some(address) => match self.heap[address] {
+                            Cell::Thunk(body, ref body_env) => {
+                                let body_env = body_env.clone();
+                                self.heap[address] = Cell::Evaluating(body, body_env.clone());
+                                self.stack.push(Frame::Update(address));
+                                self.control = Control::Closure(body, body_env);
+                            },
+                            Cell::Value(ref value) => self.control = value.clone(),
+                            // An argument that needs itself can not make progress
+                            Cell::Evaluating(_, _) => break Outcome::Stuck
+                        },
+                        None => self.control = Control::Neutral(tree, vec![])
+                    },
+                    Tree::Abs(_, id, body) => match self.stack.pop() {
+                        Some(Frame::Argument(address)) => {
+                            steps += 1;
+                            self.control = Control::Closure(body, Rc::new(Env::Bind(*id, address, env)));
+                        },
+                        Some(Frame::Update(address)) => self.heap[address] = Cell::Value(self.control.clone()),
+                        None => break Outcome::Normal
+                    }
+                },
+                Control::Neutral(head, mut arguments) => match self.stack.pop() {
+                    Some(Frame::Argument(address)) => {
+                        arguments.push(address);
+                        self.control = Control::Neutral(head, arguments);
+                    },
+                    Some(Frame::Update(address)) => self.heap[address] = Cell::Value(self.control.clone()),
+                    None => break Outcome::Normal
+                }
+            }
+        };
+
+        // Whatever is left on the stack is applied to the control
+        let mut tree = self.read_control(&self.control);
+        for frame in self.stack.iter().rev() {
+            if let Frame::Argument(address) = frame {
+                tree = Tree::App(Box::new(tree), Box::new(self.read_cell(*address)));
+            }
+        }
+        Reduction { tree, steps, eta_steps: 0, outcome }
+    }
+
+    fn read_control(&self, control : &Control<'a>) -> Tree {
+        match control {
+            Control::Closure(tree, env) => self.read_closure(tree, env, &mut vec![]),
+            Control::Neutral(head, arguments) => arguments.iter()
+                .fold((*head).clone(), |tree, address|
+                    Tree::App(Box::new(tree), Box::new(self.read_cell(*address))))
+        }
+    }
+
+    fn read_cell(&self, address : usize) -> Tree {
+        match &self.heap[address] {
+            Cell::Thunk(tree, env) | Cell::Evaluating(tree, env) => self.read_closure(tree, env, &mut vec![]),
+            Cell::Value(control) => self.read_control(control)
+        }
+    }
+
+    // Replaces the variables bound in the environment by what they point to,
+    // except those bound again by an abstraction inside the term
+    fn read_closure(&self, tree : &Tree, env : &Env, locals : &mut Vec<isize>) -> Tree {
+        match tree {
+            Tree::Var(_, id) if locals.contains(id) => tree.clone(),
+            Tree::Var(_, id) => match env.find(*id) {
+                Some(address) => self.read_cell(address),
+                None => tree.clone()
+            },
+            Tree::Abs(x, id, body) => {
+                locals.push(*id);
+                let body = self.read_closure(body, env, locals);
+                locals.pop();
+                Tree::Abs(*x, *id, Box::new(body))
+            },
+            Tree::App(left, right) => Tree::App(
+                Box::new(self.read_closure(left, env, locals)),
+                Box::new(self.read_closure(right, env, locals)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use typical::{borrow_names, parse_with_prelude};
+
+    const STRATEGIES : [Strategy; 6] = [
+        Strategy::NormalOrder, Strategy::ApplicativeOrder,
+        Strategy::CallByName, Strategy::CallByValue, Strategy::CallByNeed,
+        Strategy::Parallel
+    ];
+
+    fn normal_form(input : &str, strategy : Strategy) -> String {
+        let (tree, names) = parse_with_prelude(input).expect(input);
+        let mut reduction = tree.reduce(strategy, &Limits::steps(10000));
+        assert_eq!(reduction.outcome, Outcome::Normal, "reducing {:?} with {:?}", input, strategy);
+        // The weak strategies stop early, what is left must normalize to the same term
+        if let Strategy::CallByName | Strategy::CallByValue | Strategy::CallByNeed = strategy {
+            reduction = reduction.tree.reduce(Strategy::NormalOrder, &Limits::steps(10000));
+            assert_eq!(reduction.outcome, Outcome::Normal, "reducing {:?} with {:?}", input, strategy);
+        }
+        reduction.tree.to_de_bruijn(&borrow_names(&names)).to_string()
+    }
+
+    #[test]
+    fn capture_prone_terms() {
+        let corpus = [
+            ("(\\x.\\y.x) y", "\\z.y"),
+            ("(\\x.\\y.\\z.x y z) y z", "\\w.y z w"),
+            ("(\\x.x x) (\\y.\\z.y z)", "\\z.\\w.z w"),
+            ("(\\f.\\x.f (f x)) (\\y.\\x.y x)", "\\x.\\w.x w"),
+            ("(\\x.\\y.y x) (\\z.y)", "\\w.w (\\z.y)"),
+            ("(\\f.f (f a)) (\\x.\\y.x y)", "\\y.a y"),
+            ("(\\a.\\b.a (a b)) (\\c.\\d.c (c d))", "\\b.\\d.b (b (b (b d)))"),
+            ("2 2", "4"),
+            ("3 2", "8"),
+            ("2 3", "9"),
+            ("pow 2 3", "8"),
+            ("pred 3", "2"),
+            ("minus 5 2", "3"),
+            ("mult (plus 1 2) (pred 4)", "9"),
+            ("(\\s.s s) (\\x.\\y.x)", "\\y.\\x.\\y.x"),
+        ];
+        for (input, expected) in corpus.iter() {
+            let expected = normal_form(expected, Strategy::NormalOrder);
+            for strategy in STRATEGIES.iter() {
+                assert_eq!(normal_form(input, *strategy), expected, "reducing {:?} with {:?}", input, strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_strategies() {
+        let corpus = [
+            ("K a ((\\x.x x) (\\x.x x))", "a"),
+            ("Y (\\f.\\n.iszero n 1 (mult n (f (pred n)))) 3", "6"),
+        ];
+        for (input, expected) in corpus.iter() {
+            let expected = normal_form(expected, Strategy::NormalOrder);
+            for strategy in [Strategy::NormalOrder, Strategy::CallByName, Strategy::CallByNeed].iter() {
+                assert_eq!(normal_form(input, *strategy), expected, "reducing {:?} with {:?}", input, strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn eta_rule() {
+        let corpus = [
+            ("\\x.f x", "f", 1),
+            ("\\x.\\y.f x y", "f", 2),
+            ("\\x.x x", "\\x.x x", 0),
+            ("\\f.\\x.f x", "\\f.f", 1),
+            ("(\\g.\\x.g x) (\\y.y)", "\\y.y", 0),
+            ("\\x.(\\y.\\z.y z) f x", "f", 1),
+        ];
+        for (input, expected, eta_steps) in corpus.iter() {
+            let (tree, names) = parse_with_prelude(input).expect(input);
+            let names = borrow_names(&names);
+            let (expected, expected_names) = parse_with_prelude(expected).expect(expected);
+            let expected = expected.to_de_bruijn(&borrow_names(&expected_names)).to_string();
+            for strategy in [Strategy::NormalOrder, Strategy::ApplicativeOrder, Strategy::Parallel].iter() {
+                let reduction = tree.reduce_with(*strategy, Rules::BetaEta, &Limits::default());
+                assert_eq!(reduction.tree.to_de_bruijn(&names).to_string(), expected, "reducing {:?} with {:?}", input, strategy);
+                assert_eq!(reduction.eta_steps, *eta_steps, "reducing {:?} with {:?}", input, strategy);
+            }
+            // Beta alone leaves them
+            let reduction = tree.reduce_with(Strategy::NormalOrder, Rules::Beta, &Limits::default());
+            assert_eq!(reduction.eta_steps, 0);
+        }
+    }
+
+    #[test]
+    fn traces() {
+        use typical::Branch::*;
+
+        let input = "(\\x.x x) (I I)";
+        let (tree, names) = parse_with_prelude(input).expect(input);
+        let names = borrow_names(&names);
+
+        // A trace ends where the reduction does
+        for strategy in STRATEGIES.iter().filter(|strategy| **strategy != Strategy::CallByNeed) {
+            let reduction = tree.reduce(*strategy, &Limits::default());
+            let mut trace = tree.trace(*strategy, &Limits::default());
+            let redexes : usize = trace.by_ref().map(|step| step.redexes.len()).sum();
+            assert_eq!(redexes, reduction.steps, "tracing with {:?}", strategy);
+            assert_eq!(trace.steps(), reduction.steps, "tracing with {:?}", strategy);
+            assert_eq!(trace.outcome(), Some(Outcome::Normal));
+            assert_eq!(trace.tree, reduction.tree, "tracing with {:?}", strategy);
+        }
+
+        let steps : Vec<_> = tree.trace(Strategy::NormalOrder, &Limits::default()).collect();
+        let paths : Vec<_> = steps.iter().map(|step| step.redexes.clone()).collect();
+        assert_eq!(paths, vec![vec![vec![]], vec![vec![Function]], vec![vec![]], vec![vec![]]]);
+        let marked : Vec<_> = steps.iter()
+            .map(|step| step.tree.to_string_marked(&names, &step.redexes, "[", "]"))
+            .collect();
+        assert_eq!(marked, vec![
+            "[(λx.x x) ((λx.x) λx.x)]",
+            "([(λx.x) λx.x]) ((λx.x) λx.x)",
+            "[(λx.x) ((λx.x) λx.x)]",
+            "[(λx.x) λx.x]",
+        ]);
+
+        let steps : Vec<_> = tree.trace(Strategy::Parallel, &Limits::default()).collect();
+        assert_eq!(steps[0].redexes, vec![vec![], vec![Argument]]);
+
+        let steps : Vec<_> = tree.trace(Strategy::NormalOrder, &Limits::steps(2)).collect();
+        assert_eq!(steps.len(), 2);
+    }
+
+    #[test]
+    fn step_counts() {
+        let corpus = [
+            ("(\\x.x x) (I I)", [4, 3, 4, 3, 3, 3]),
+            ("K a (I b)", [2, 3, 2, 3, 2, 3]),
+        ];
+        for (input, counts) in corpus.iter() {
+            let tree = parse_with_prelude(input).expect(input).0;
+            for (strategy, count) in STRATEGIES.iter().zip(counts.iter()) {
+                assert_eq!(tree.reduce(*strategy, &Limits::default()).steps, *count,
+                    "reducing {:?} with {:?}", input, strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn size_limit() {
+        // Every step leaves a larger term, with no step limit only the size stops it
+        let input = "(\\x.x x x) (\\x.x x x)";
+        let tree = parse_with_prelude(input).expect(input).0;
+        for strategy in STRATEGIES.iter() {
+            let reduction = tree.reduce(*strategy, &Limits::size(100));
+            assert_eq!(reduction.outcome, Outcome::SizeLimit, "reducing {:?} with {:?}", input, strategy);
+            let mut trace = tree.trace(*strategy, &Limits::size(100));
+            trace.by_ref().count();
+            assert_eq!(trace.outcome(), Some(Outcome::SizeLimit), "tracing with {:?}", strategy);
+        }
+    }
+}
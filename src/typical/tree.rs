@@ -1,6 +1,6 @@
-use std::collections::{HashMap};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::str;
-use std::time::{Instant, Duration};
 
 #[derive(Debug, Clone)]
 pub enum Tree {
@@ -9,123 +9,147 @@ pub enum Tree {
     App(Box<Tree>, Box<Tree>),
 }
 
+// A step from a node to one of its subterms, a path of these from the root
+// locates a subterm
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Branch {
+    Body,
+    Function,
+    Argument
+}
+
+// The subterms to surround with `open` and `close` when printing
+struct Marks<'a> {
+    paths : &'a [Vec<Branch>],
+    open : &'a str,
+    close : &'a str
+}
+
 impl Tree {
 
     #[inline(always)]
     pub fn to_string(&self, names : &HashMap<isize, &str>) -> String {
-        self.to_string_helper(false, names)
+        let marks = Marks { paths: &[], open: "", close: "" };
+        self.to_string_helper(false, names, &marks, &mut vec![], &mut vec![])
+    }
+
+    // Prints the term with the subterms at the given paths between `open` and
+    // `close`, for instance the redexes of a `TraceStep`
+    pub fn to_string_marked(&self,
+        names : &HashMap<isize, &str>,
+        paths : &[Vec<Branch>],
+        open : &str,
+        close : &str) -> String
+    {
+        let marks = Marks { paths, open, close };
+        self.to_string_helper(false, names, &marks, &mut vec![], &mut vec![])
     }
 
-    fn to_string_helper(&self, in_abstraction : bool, names : &HashMap<isize, &str>) -> String {
+    // The scope holds the names printed for the binders around the subterm.
+    // A binder whose name would capture a variable of its body is printed
+    // with primes added until it no longer does.
+    fn to_string_helper(&self,
+        in_abstraction : bool,
+        names : &HashMap<isize, &str>,
+        marks : &Marks,
+        path : &mut Vec<Branch>,
+        scope : &mut Vec<(isize, String)>) -> String
+    {
         let mut result = String::new();
+        let marked = marks.paths.contains(path);
+        if marked { result.push_str(marks.open); }
         match self {
-            Tree::Var(id, _) => result.push_str(names.get(id).unwrap_or(&"MissingId")),
-            Tree::Abs(id, _, expr) => {
+            Tree::Var(id, bound_id) => result.push_str(&Tree::printed_name(*id, *bound_id, names, scope)),
+            Tree::Abs(id, bound_id, expr) => {
+                // A marked body can not be merged into the binders before it
+                path.push(Branch::Body);
+                let continued = matches!(**expr, Tree::Abs(_, _, _)) && !marks.paths.contains(path);
+
                 if !in_abstraction {
-                    result.push_str(&"λ");
+                    result.push('λ');
                 }
-                
-                let continued = if let Tree::Abs(_, _, _) = **expr {
-                    true
-                } else {
-                    false
-                };
 
-                result.push_str(names.get(id).unwrap_or(&"MissingId"));
+                let mut taken = HashSet::new();
+                expr.printed_free_names(*bound_id, names, scope, &mut vec![], &mut taken);
+                let mut name = names.get(id).unwrap_or(&"MissingId").to_string();
+                while taken.contains(&name) {
+                    name.push('\'');
+                }
+
+                result.push_str(&name);
                 if continued {
                     result.push(' ');
                 } else {
                     result.push('.');
                 }
 
-                let mut temp = expr.to_string_helper(continued, names);
+                scope.push((*bound_id, name));
+                let mut temp = expr.to_string_helper(continued, names, marks, path, scope);
+                scope.pop();
+                path.pop();
                 result.extend(temp.drain(..));
             },
             Tree::App(left, right) => {
-                let left_in_parens = if let Tree::Var(_, _) = **left {
-                    false
-                } else {
-                    true
-                };
+                let left_in_parens = !matches!(**left, Tree::Var(_, _));
 
                 if left_in_parens { result.push('('); }
-                let mut temp = left.to_string_helper(false, names);
+                path.push(Branch::Function);
+                let mut temp = left.to_string_helper(false, names, marks, path, scope);
+                path.pop();
                 result.extend(temp.drain(..));
                 if left_in_parens { result.push(')'); }
 
+                let right_in_parens = matches!(**right, Tree::App(_, _));
+
                 result.push(' ');
-                let mut temp = right.to_string_helper(false, names);
+                if right_in_parens { result.push('('); }
+                path.push(Branch::Argument);
+                let mut temp = right.to_string_helper(false, names, marks, path, scope);
+                path.pop();
                 result.extend(temp.drain(..));
+                if right_in_parens { result.push(')'); }
             }
         }
+        if marked { result.push_str(marks.close); }
         result
     }
 
-    fn is_normal(&self) -> bool {
-        let mut result = true;
-        match self {
-            Tree::App(left, right) => {
-                if let Tree::Abs(_, _, _) = **left {
-                    result &= false;
-                } else {
-                    result &= left.is_normal();
-                    result &= right.is_normal();
-                }
-            },
-            Tree::Abs(_, _, expr) => {
-                result &= expr.is_normal();
-            }
-            Tree::Var(_, _) => { }
+    // Bound variables take the name printed for their binder, free ones keep
+    // their own
+    fn printed_name(id : isize, bound_id : isize, names : &HashMap<isize, &str>, scope : &[(isize, String)]) -> String {
+        match scope.iter().rev().find(|(bound, _)| *bound == bound_id) {
+            Some((_, name)) => name.clone(),
+            None => names.get(&id).unwrap_or(&"MissingId").to_string()
         }
-        result
     }
 
-    fn substitute(tree : Tree, argument : Tree, id : isize) -> Tree {
-        match tree {
-            Tree::Var(_, bound_id) if id == bound_id => {
-                argument
+    // The printed names of the variables of this subterm that are bound
+    // neither inside it nor by the binder `bound_id`
+    fn printed_free_names(&self,
+        bound_id : isize,
+        names : &HashMap<isize, &str>,
+        scope : &[(isize, String)],
+        inner : &mut Vec<isize>,
+        taken : &mut HashSet<String>)
+    {
+        match self {
+            Tree::Var(id, bound) => {
+                if *bound != bound_id && !inner.contains(bound) {
+                    taken.insert(Tree::printed_name(*id, *bound, names, scope));
+                }
+            },
+            Tree::Abs(_, bound, body) => {
+                inner.push(*bound);
+                body.printed_free_names(bound_id, names, scope, inner, taken);
+                inner.pop();
             },
-            Tree::Var(x, y) => Tree::Var(x, y),
-            Tree::Abs(x, y, expr) => Tree::Abs(x, y, Box::new(Tree::substitute(*expr, argument, id))),
-            Tree::App(left, right) =>
-                Tree::App(Box::new(Tree::substitute(*left, argument.clone(), id)),
-                    Box::new(Tree::substitute(*right, argument, id)))
-        }
-    }
-
-    fn reduction_step(tree : Tree) -> Tree {
-        match tree {
-            Tree::Var(x, y) => Tree::Var(x, y),
-            Tree::Abs(x, y, expr) => Tree::Abs(x, y, Box::new(Tree::reduction_step(*expr))),
             Tree::App(left, right) => {
-                if let Tree::Abs(_, id, expr) = *left {
-                    Tree::substitute(*expr, *right, id)
-                } else {
-                    Tree::App(Box::new(Tree::reduction_step(*left)),
-                        Box::new(Tree::reduction_step(*right)))
-                }
+                left.printed_free_names(bound_id, names, scope, inner, taken);
+                right.printed_free_names(bound_id, names, scope, inner, taken);
             }
         }
     }
 
-    pub fn reduce_with_timeout(tree : Tree, timeout : Duration) -> Result<(Tree, Duration), Tree> {
-        let timer = Instant::now();
-        let mut result = tree;
-        let mut finished = false;
-
-        loop {
-            result = Tree::reduction_step(result);
-            if result.is_normal() { finished = true; break; }
-            if timer.elapsed() > timeout { break; }
-        }
-
-        if finished {
-            Ok((result, timer.elapsed()))
-        } else {
-            Err(result)
-        }
-    }
-
     #[inline]
     pub fn canonicalize_names(&mut self) {
         let mut id = 0;
@@ -150,11 +174,11 @@ impl Tree {
                 *id += 1;
                 *bound_id = *id;
                 {
-                    let mut stack = bound_names.entry(*global_id).or_insert(vec![]);
+                    let stack = bound_names.entry(*global_id).or_insert(vec![]);
                     stack.push(*id);
                 }
                 expr.canonicalize_names_helper(id, bound_names);
-                let mut stack = bound_names.entry(*global_id).or_insert(vec![]);
+                let stack = bound_names.entry(*global_id).or_insert(vec![]);
                 stack.pop();
             },
             Tree::App(left, right) => {
@@ -164,3 +188,180 @@ impl Tree {
         }
     }
 }
+
+// Terms are equal when they only differ in the ids of their binders. Bound
+// variables are compared by the number of binders up to their own, free
+// variables by their name id. A name id only stands for the same name in
+// trees from the same parser, so trees from different ones are compared by
+// name through `to_de_bruijn` with the names map of each.
+impl PartialEq for Tree {
+    fn eq(&self, other : &Tree) -> bool {
+        Tree::alpha_equivalent(self, other, &mut vec![], &mut vec![])
+    }
+}
+
+impl Eq for Tree {}
+
+impl Hash for Tree {
+    fn hash<H : Hasher>(&self, state : &mut H) {
+        self.for_each_node(&mut vec![], &mut |tag, value| {
+            state.write_u8(tag);
+            state.write_isize(value);
+        });
+    }
+}
+
+impl Tree {
+
+    fn alpha_equivalent(left : &Tree, right : &Tree,
+        left_scope : &mut Vec<isize>,
+        right_scope : &mut Vec<isize>) -> bool
+    {
+        match (left, right) {
+            (Tree::Var(x, i), Tree::Var(y, j)) => {
+                let left_index = left_scope.iter().rev().position(|bound| bound == i);
+                let right_index = right_scope.iter().rev().position(|bound| bound == j);
+                match (left_index, right_index) {
+                    (None, None) => x == y,
+                    (left_index, right_index) => left_index == right_index
+                }
+            },
+            (Tree::Abs(_, i, left_body), Tree::Abs(_, j, right_body)) => {
+                left_scope.push(*i);
+                right_scope.push(*j);
+                let result = Tree::alpha_equivalent(left_body, right_body, left_scope, right_scope);
+                left_scope.pop();
+                right_scope.pop();
+                result
+            },
+            (Tree::App(a, b), Tree::App(c, d)) =>
+                Tree::alpha_equivalent(a, c, left_scope, right_scope)
+                && Tree::alpha_equivalent(b, d, left_scope, right_scope),
+            _ => false
+        }
+    }
+
+    // Visits the nodes in prefix order as they look without binder ids
+    fn for_each_node<F : FnMut(u8, isize)>(&self, scope : &mut Vec<isize>, f : &mut F) {
+        match self {
+            Tree::Var(x, id) => match scope.iter().rev().position(|bound| bound == id) {
+                Some(index) => f(0, index as isize),
+                None => f(1, *x)
+            },
+            Tree::Abs(_, id, body) => {
+                f(2, 0);
+                scope.push(*id);
+                body.for_each_node(scope, f);
+                scope.pop();
+            },
+            Tree::App(left, right) => {
+                f(3, 0);
+                left.for_each_node(scope, f);
+                right.for_each_node(scope, f);
+            }
+        }
+    }
+
+    // The number of variables, abstractions and applications
+    pub fn size(&self) -> usize {
+        match self {
+            Tree::Var(_, _) => 1,
+            Tree::Abs(_, _, body) => 1 + body.size(),
+            Tree::App(left, right) => 1 + left.size() + right.size()
+        }
+    }
+
+    // A hash that agrees with equality and does not change between runs or
+    // builds, unlike the one `Hash` gives through the standard hashers
+    pub fn structural_hash(&self) -> u64 {
+        // 64 bit FNV-1a
+        let mut hash : u64 = 0xcbf2_9ce4_8422_2325;
+        self.for_each_node(&mut vec![], &mut |tag, value| {
+            let bytes = (value as i64).to_le_bytes();
+            for byte in [tag].iter().chain(bytes.iter()) {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        });
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash(tree : &Tree) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        tree.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn var(name : isize, bound : isize) -> Tree { Tree::Var(name, bound) }
+    fn abs(name : isize, bound : isize, body : Tree) -> Tree { Tree::Abs(name, bound, Box::new(body)) }
+    fn app(left : Tree, right : Tree) -> Tree { Tree::App(Box::new(left), Box::new(right)) }
+
+    #[test]
+    fn binders_are_renamed_when_they_capture() {
+        let names : HashMap<isize, &str> = [(1, "x"), (2, "f")].iter().cloned().collect();
+        let corpus = [
+            (abs(1, 1, abs(1, 2, app(var(1, 1), var(1, 2)))), "λx x'.x x'"),
+            (abs(1, 1, abs(1, 2, abs(1, 3, app(app(var(1, 1), var(1, 2)), var(1, 3))))), "λx x' x''.(x x') x''"),
+            (abs(1, 1, abs(1, 2, var(1, 1))), "λx x'.x"),
+            (abs(1, 1, app(var(1, 0), var(1, 1))), "λx'.x x'"),
+            (abs(2, 1, abs(1, 2, app(var(2, 1), abs(1, 3, app(var(1, 2), var(1, 3)))))), "λf x.f λx'.x x'"),
+            // Shadowing alone captures nothing and is left alone
+            (abs(1, 1, app(abs(1, 2, var(1, 2)), var(1, 1))), "λx.(λx.x) x"),
+            (abs(1, 1, abs(1, 2, var(1, 2))), "λx x.x"),
+        ];
+        for (tree, expected) in corpus.iter() {
+            assert_eq!(tree.to_string(&names), *expected, "printing {:?}", tree);
+        }
+    }
+
+    #[test]
+    fn equal_up_to_binder_ids() {
+        // λx y.x y z, with different binder ids and names
+        let left = abs(1, 1, abs(2, 2, app(app(var(1, 1), var(2, 2)), var(3, 0))));
+        let right = abs(4, 7, abs(5, 9, app(app(var(4, 7), var(5, 9)), var(3, 0))));
+        assert_eq!(left, right);
+        assert_eq!(hash(&left), hash(&right));
+        assert_eq!(left.structural_hash(), right.structural_hash());
+
+        // λx y.x against λx y.y
+        let first = abs(1, 1, abs(2, 2, var(1, 1)));
+        let second = abs(1, 1, abs(2, 2, var(2, 2)));
+        assert_ne!(first, second);
+        assert_ne!(first.structural_hash(), second.structural_hash());
+
+        // λx x.x bound by the inner binder against the outer one
+        let inner = abs(1, 1, abs(1, 2, var(1, 2)));
+        let outer = abs(1, 1, abs(1, 2, var(1, 1)));
+        assert_ne!(inner, outer);
+
+        // Free variables are compared by name id
+        assert_ne!(var(1, 0), var(2, 0));
+        assert_ne!(abs(1, 1, var(1, 1)), abs(1, 1, var(1, 0)));
+    }
+
+    #[test]
+    fn free_variables_from_different_parsers() {
+        // λx.y where two parsers numbered y differently
+        let left = abs(1, 1, var(2, 0));
+        let right = abs(1, 1, var(3, 0));
+        let left_names : HashMap<isize, &str> = [(1, "x"), (2, "y")].iter().cloned().collect();
+        let right_names : HashMap<isize, &str> = [(1, "x"), (3, "y")].iter().cloned().collect();
+        assert_ne!(left, right);
+        assert_ne!(hash(&left), hash(&right));
+        assert_eq!(left.to_de_bruijn(&left_names), right.to_de_bruijn(&right_names));
+    }
+
+    #[test]
+    fn structural_hash_is_stable() {
+        let identity = abs(1, 1, var(1, 1));
+        // FNV-1a of the tags and values of λ.0, it must not change between builds
+        assert_eq!(identity.structural_hash(), 0x17e6_59f3_ae7f_adff);
+        assert_eq!(abs(5, 3, var(5, 3)).structural_hash(), 0x17e6_59f3_ae7f_adff);
+    }
+}
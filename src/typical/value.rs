@@ -0,0 +1,178 @@
+use std::fmt;
+
+use typical::Tree;
+
+// A data value recognized in a normal form
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(usize),
+    Boolean(bool),
+    List(Vec<Value>)
+}
+
+// What a normal form is expected to encode. `false`, `0` and `[]` share the
+// normal form `λa b.b`, only the expected type tells them apart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueType {
+    Number,
+    Boolean,
+    List(Box<ValueType>)
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 { write!(f, ",")?; }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl Tree {
+
+    // Guesses the type from the shape of the term, trying numbers, then
+    // booleans, then lists. As `λa b.b` is taken for `0` this never gives
+    // `false` or `[]`, use `decode_as` for those. Variables are compared by
+    // bound id, so the names must be canonical.
+    pub fn decode(&self) -> Option<Value> {
+        let (outer, inner, body) = self.binders()?;
+        Tree::decode_number(outer, inner, body)
+            .or_else(|| Tree::decode_boolean(outer, inner, body))
+            .or_else(|| Tree::decode_list(outer, inner, body, None))
+    }
+
+    pub fn decode_as(&self, expected : &ValueType) -> Option<Value> {
+        let (outer, inner, body) = self.binders()?;
+        match expected {
+            ValueType::Number => Tree::decode_number(outer, inner, body),
+            ValueType::Boolean => Tree::decode_boolean(outer, inner, body),
+            ValueType::List(element) => Tree::decode_list(outer, inner, body, Some(element))
+        }
+    }
+
+    // Every value is encoded under two distinct binders
+    fn binders(&self) -> Option<(isize, isize, &Tree)> {
+        match self {
+            Tree::Abs(_, outer, expr) => match **expr {
+                Tree::Abs(_, inner, ref body) if inner != *outer => Some((*outer, inner, body)),
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    // Church numerals, λf x.f (... (f x))
+    fn decode_number(f : isize, x : isize, body : &Tree) -> Option<Value> {
+        let mut n = 0;
+        let mut current = body;
+        loop {
+            match current {
+                Tree::Var(_, id) if *id == x => return Some(Value::Number(n)),
+                Tree::App(left, right) => match **left {
+                    Tree::Var(_, id) if id == f => { n += 1; current = right; },
+                    _ => return None
+                },
+                _ => return None
+            }
+        }
+    }
+
+    // Church booleans, λt f.t and λt f.f
+    fn decode_boolean(t : isize, f : isize, body : &Tree) -> Option<Value> {
+        match body {
+            Tree::Var(_, id) if *id == t => Some(Value::Boolean(true)),
+            Tree::Var(_, id) if *id == f => Some(Value::Boolean(false)),
+            _ => None
+        }
+    }
+
+    // Right folds, λc n.c a (c b n), with elements of the given type or
+    // guessed when there is none
+    fn decode_list(c : isize, n : isize, body : &Tree, element : Option<&ValueType>) -> Option<Value> {
+        let mut values = vec![];
+        let mut current = body;
+        loop {
+            match current {
+                Tree::Var(_, id) if *id == n => return Some(Value::List(values)),
+                Tree::App(left, tail) => match **left {
+                    Tree::App(ref cons, ref head) => match **cons {
+                        Tree::Var(_, id) if id == c => {
+                            let value = match element {
+                                Some(element) => head.decode_as(element),
+                                None => head.decode()
+                            };
+                            values.push(value?);
+                            current = tail;
+                        },
+                        _ => return None
+                    },
+                    _ => return None
+                },
+                _ => return None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use typical::parse_with_prelude;
+
+    fn parse(input : &str) -> Tree {
+        parse_with_prelude(input).expect(input).0
+    }
+
+    fn numbers(values : &[usize]) -> Value {
+        Value::List(values.iter().map(|n| Value::Number(*n)).collect())
+    }
+
+    #[test]
+    fn decode_guesses_the_type() {
+        let corpus = [
+            ("0", Some(Value::Number(0))),
+            ("3", Some(Value::Number(3))),
+            ("true", Some(Value::Boolean(true))),
+            // The same term as 0
+            ("false", Some(Value::Number(0))),
+            ("\\c n.c 1 (c 2 n)", Some(numbers(&[1, 2]))),
+            ("\\c n.c true n", Some(Value::List(vec![Value::Boolean(true)]))),
+            ("\\x.x", None),
+            ("\\x y.x y y", None),
+            ("\\x x.x", Some(Value::Number(0))),
+        ];
+        for (input, expected) in corpus.iter() {
+            assert_eq!(parse(input).decode(), *expected, "decoding {:?}", input);
+        }
+    }
+
+    #[test]
+    fn decode_as_takes_the_expected_type() {
+        let list = |element : ValueType| ValueType::List(Box::new(element));
+        let corpus = [
+            ("false", ValueType::Boolean, Some(Value::Boolean(false))),
+            ("true", ValueType::Boolean, Some(Value::Boolean(true))),
+            ("0", ValueType::Boolean, Some(Value::Boolean(false))),
+            ("false", ValueType::Number, Some(Value::Number(0))),
+            ("2", ValueType::Boolean, None),
+            ("true", ValueType::Number, None),
+            ("0", list(ValueType::Number), Some(numbers(&[]))),
+            ("\\c n.c 0 (c 2 n)", list(ValueType::Number), Some(numbers(&[0, 2]))),
+            ("\\c n.c false (c true n)", list(ValueType::Boolean),
+                Some(Value::List(vec![Value::Boolean(false), Value::Boolean(true)]))),
+            ("\\c n.c 0 n", list(list(ValueType::Number)), Some(Value::List(vec![numbers(&[])]))),
+            ("\\c n.c 2 n", list(ValueType::Boolean), None),
+        ];
+        for (input, expected_type, expected) in corpus.iter() {
+            assert_eq!(parse(input).decode_as(expected_type), *expected, "decoding {:?} as {:?}", input, expected_type);
+        }
+    }
+}
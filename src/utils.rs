@@ -1,4 +1,5 @@
 use cfg_if::cfg_if;
+use serde_json::Value;
 
 cfg_if! {
     // When the `console_error_panic_hook` feature is enabled, we can call the
@@ -12,6 +13,19 @@ cfg_if! {
     }
 }
 
+// Errors are written as {"error": {kind, message, ...}} with the fields
+// particular to each kind of error after the first two
+pub fn error_json(kind : &str, message : &str, fields : Value) -> String {
+    let mut error = json!({
+        "kind": kind,
+        "message": message
+    });
+    if let (Some(error), Value::Object(fields)) = (error.as_object_mut(), fields) {
+        error.extend(fields);
+    }
+    json!({ "error": error }).to_string()
+}
+
 cfg_if! {
     // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
     // allocator.
@@ -0,0 +1,21 @@
+// Parsing and printing the way the integration tests all do it. Not every
+// test uses all of it.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use olette::abstract_algorithm::Net;
+use olette::typical::{borrow_names, parse_with_prelude, Tree};
+
+pub fn parse(source : &str) -> (Tree, HashMap<isize, String>) {
+    parse_with_prelude(source).unwrap_or_else(|error| panic!("{}\n{}", source, error))
+}
+
+pub fn build(source : &str) -> (Net, HashMap<isize, String>) {
+    let (tree, names) = parse(source);
+    (Net::from_tree(&tree), names)
+}
+
+pub fn show(tree : &Tree, names : &HashMap<isize, String>) -> String {
+    tree.to_string(&borrow_names(names))
+}
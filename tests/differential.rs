@@ -0,0 +1,154 @@
+// Reduces random closed terms as a tree and as a net and checks that both
+// reach the same normal form. `OLETTE_SEED` and `OLETTE_CASES` change which
+// terms and how many are tried.
+extern crate olette;
+
+mod common;
+mod generator;
+
+use std::env;
+use std::time::Duration;
+
+use olette::abstract_algorithm::Net;
+use olette::limits::{Limits, Outcome};
+use olette::typical::{DeBruijn, Strategy};
+
+use common::show;
+use generator::{Rng, Shape};
+
+const TREE_STEPS : usize = 1_000;
+const TREE_SIZE : usize = 10_000;
+const NET_INTERACTIONS : usize = 20_000;
+
+fn setting(name : &str, default : u64) -> u64 {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+// Terms the tree can not normalize within its budget are left out, there is
+// nothing to compare them by. Any other term the net has to normalize too,
+// and within its own budget.
+fn agree(term : &DeBruijn) -> Result<(), String> {
+    let (tree, names) = generator::to_tree(term);
+    let reduction = tree.reduce(Strategy::NormalOrder, &Limits::new(Some(TREE_STEPS), None, Some(TREE_SIZE)));
+    if reduction.outcome != Outcome::Normal {
+        return Ok(());
+    }
+
+    let mut net = Net::from_tree(&tree);
+    let report = net.normalize(&Limits::steps(NET_INTERACTIONS))
+        .map_err(|error| format!("the net failed: {}", error))?;
+    if report.outcome != Outcome::Normal {
+        return Err(format!("the tree reaches a normal form but the net ends {:?}", report.outcome));
+    }
+    let result = net.to_tree()
+        .map_err(|error| format!("the net could not be read back: {}", error))?;
+    if result == reduction.tree {
+        Ok(())
+    } else {
+        Err(format!("the tree gives {} but the net gives {}",
+            show(&reduction.tree, &names), show(&result, &names)))
+    }
+}
+
+fn check(seed : u64, shape : Shape, sizes : (usize, usize)) {
+    let mut rng = Rng::new(seed);
+    let cases = setting("OLETTE_CASES", 200);
+    for _ in 0..cases {
+        let size = sizes.0 + rng.below(sizes.1 - sizes.0 + 1);
+        let term = generator::term(&mut rng, &shape, size);
+        if let Err(message) = agree(&term) {
+            let (term, message) = generator::minimize(term, message, agree);
+            let (tree, names) = generator::to_tree(&term);
+            panic!("Reducing {} (seed {}): {}", show(&tree, &names), seed, message);
+        }
+    }
+}
+
+#[test]
+fn tree_and_net_agree() {
+    check(setting("OLETTE_SEED", 1), Shape::default(), (2, 24));
+}
+
+// Many redexes, with arguments shared and erased more often
+#[test]
+fn tree_and_net_agree_on_applications() {
+    let shape = Shape { abstraction: 1, application: 3, redex: 60 };
+    check(setting("OLETTE_SEED", 2), shape, (8, 28));
+}
+
+// Deep binders, with variables bound far away
+#[test]
+fn tree_and_net_agree_on_abstractions() {
+    let shape = Shape { abstraction: 3, application: 2, redex: 30 };
+    check(setting("OLETTE_SEED", 3), shape, (8, 28));
+}
+
+// Both have a normal form, but only when the divergent part is left alone
+#[test]
+fn net_only_reduces_what_the_normal_form_needs() {
+    let terms = [
+        (r"K a ((\x.x x) (\x.x x))", "a"),
+        (r"Y (\f.\n.iszero n 1 (mult n (f (pred n)))) 3", "λf x.f (f (f (f (f (f x)))))")
+    ];
+    for (source, expected) in terms.iter() {
+        let (tree, names) = common::parse(source);
+
+        let reduction = tree.reduce(Strategy::NormalOrder, &Limits::steps(TREE_STEPS));
+        assert_eq!(reduction.outcome, Outcome::Normal, "{}", source);
+        let mut net = Net::from_tree(&tree);
+        let report = net.normalize(&Limits::steps(NET_INTERACTIONS)).unwrap();
+        assert_eq!(report.outcome, Outcome::Normal, "{}", source);
+        let result = net.to_tree().unwrap();
+        assert_eq!(result, reduction.tree, "{}", source);
+        assert_eq!(show(&result, &names), *expected);
+    }
+}
+
+// Each interaction walks only what changed, so a product of two large
+// numerals, and the walk through a large numeral, stay well within a budget
+#[test]
+fn net_normalizes_large_terms_in_budget() {
+    let terms = [("mult 20 20", 2_000), ("600", 0)];
+    for (source, interactions) in terms.iter() {
+        let (mut net, _) = common::build(source);
+        let limits = Limits::new(Some(*interactions), Some(Duration::from_secs(5)), None);
+        let report = net.normalize(&limits).unwrap();
+        assert_eq!(report.outcome, Outcome::Normal, "{}", source);
+    }
+}
+
+#[test]
+fn generated_terms_are_closed_and_sized() {
+    let mut rng = Rng::new(4);
+    for size in 2..40 {
+        let term = generator::term(&mut rng, &Shape::default(), size);
+        assert_eq!(generator::size(&term), size);
+        let (tree, names) = generator::to_tree(&term);
+        assert_eq!(tree.to_de_bruijn(&names.iter().map(|(id, name)| (*id, name.as_str())).collect()), term);
+    }
+}
+
+#[test]
+fn shrinking_finds_a_smallest_failure() {
+    // Fails on every term with an application whose argument is an abstraction
+    fn property(term : &DeBruijn) -> Result<(), String> {
+        match term {
+            DeBruijn::App(_, right) if matches!(**right, DeBruijn::Abs(_, _)) => Err("found".to_string()),
+            DeBruijn::App(left, right) => property(left).and(property(right)),
+            DeBruijn::Abs(_, body) => property(body),
+            _ => Ok(())
+        }
+    }
+    let mut rng = Rng::new(5);
+    let mut found = 0;
+    for _ in 0..100 {
+        let term = generator::term(&mut rng, &Shape::default(), 30);
+        if let Err(message) = property(&term) {
+            let (term, _) = generator::minimize(term, message, property);
+            // λx.x (λy.y) is as small as it gets
+            assert_eq!(generator::size(&term), 5, "{}", term);
+            found += 1;
+        }
+    }
+    assert!(found > 0);
+}
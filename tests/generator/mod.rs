@@ -0,0 +1,195 @@
+// Random closed lambda terms and the shrinking of the ones a property fails
+// on, without depending on a property testing crate. Not every test uses
+// all of it.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use olette::typical::{borrow_names, DeBruijn, Tree};
+
+// A xorshift generator, the same seed always gives the same terms
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed : u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, bound : usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+// How likely an abstraction is against an application when both fit, and
+// the percentage of applications made into redexes
+#[derive(Debug, Clone, Copy)]
+pub struct Shape {
+    pub abstraction : usize,
+    pub application : usize,
+    pub redex : usize
+}
+
+impl Default for Shape {
+    fn default() -> Shape {
+        Shape { abstraction: 2, application: 3, redex: 30 }
+    }
+}
+
+// A closed term with exactly `size` nodes
+pub fn term(rng : &mut Rng, shape : &Shape, size : usize) -> DeBruijn {
+    term_helper(rng, shape, size.max(2), 0)
+}
+
+fn term_helper(rng : &mut Rng, shape : &Shape, size : usize, depth : usize) -> DeBruijn {
+    if size == 1 {
+        return DeBruijn::Var(rng.below(depth));
+    }
+    // Outside of every binder the only way to close the term is to add one
+    let application = size >= 3 && depth > 0
+        && rng.below(shape.abstraction + shape.application) >= shape.abstraction;
+    if application {
+        let left = 1 + rng.below(size - 2);
+        let function = if left >= 2 && rng.below(100) < shape.redex {
+            DeBruijn::Abs(name(depth), Box::new(term_helper(rng, shape, left - 1, depth + 1)))
+        } else {
+            term_helper(rng, shape, left, depth)
+        };
+        DeBruijn::App(
+            Box::new(function),
+            Box::new(term_helper(rng, shape, size - 1 - left, depth)))
+    } else {
+        DeBruijn::Abs(name(depth), Box::new(term_helper(rng, shape, size - 1, depth + 1)))
+    }
+}
+
+// Binders are named after their depth, shrinking can move them so `names`
+// collects the ones that are there
+fn name(depth : usize) -> String {
+    format!("x{}", depth)
+}
+
+pub fn names(term : &DeBruijn) -> HashMap<isize, String> {
+    let mut names = vec![];
+    binder_names(term, &mut names);
+    names.sort();
+    names.dedup();
+    names.into_iter().enumerate().map(|(index, name)| (index as isize + 1, name)).collect()
+}
+
+fn binder_names(term : &DeBruijn, names : &mut Vec<String>) {
+    match term {
+        DeBruijn::Abs(name, body) => {
+            names.push(name.clone());
+            binder_names(body, names);
+        },
+        DeBruijn::App(left, right) => {
+            binder_names(left, names);
+            binder_names(right, names);
+        },
+        _ => {}
+    }
+}
+
+// The binders are renamed after their depth first, so none shadows another
+pub fn to_tree(term : &DeBruijn) -> (Tree, HashMap<isize, String>) {
+    let term = rename(term, 0);
+    let names = names(&term);
+    let tree = term.to_tree(&borrow_names(&names));
+    (tree, names)
+}
+
+fn rename(term : &DeBruijn, depth : usize) -> DeBruijn {
+    match term {
+        DeBruijn::Abs(_, body) => DeBruijn::Abs(name(depth), Box::new(rename(body, depth + 1))),
+        DeBruijn::App(left, right) => DeBruijn::App(
+            Box::new(rename(left, depth)),
+            Box::new(rename(right, depth))),
+        term => term.clone()
+    }
+}
+
+pub fn size(term : &DeBruijn) -> usize {
+    match term {
+        DeBruijn::Abs(_, body) => 1 + size(body),
+        DeBruijn::App(left, right) => 1 + size(left) + size(right),
+        _ => 1
+    }
+}
+
+// Closed terms with fewer nodes than `term`, or as many and variables bound
+// closer, the likeliest to be much smaller first
+pub fn shrink(term : &DeBruijn) -> Vec<DeBruijn> {
+    let mut candidates = vec![];
+    shrink_helper(term, 0, &mut |candidate| candidates.push(candidate));
+    candidates
+}
+
+fn shrink_helper(term : &DeBruijn, depth : usize, emit : &mut dyn FnMut(DeBruijn)) {
+    let size = size(term);
+    // Replacing the whole term by something that fits where it is, a variable
+    // by one bound closer so that fewer binders are used
+    let variables = match term {
+        DeBruijn::Var(index) => *index,
+        _ => depth
+    };
+    for index in 0..variables {
+        emit(DeBruijn::Var(index));
+    }
+    if size > 2 {
+        emit(DeBruijn::Abs(name(depth), Box::new(DeBruijn::Var(0))));
+    }
+    match term {
+        DeBruijn::Abs(name, body) => {
+            if let Some(body) = unbind(body, 0) {
+                emit(body);
+            }
+            shrink_helper(body, depth + 1, &mut |body| emit(DeBruijn::Abs(name.clone(), Box::new(body))));
+        },
+        DeBruijn::App(left, right) => {
+            emit((**left).clone());
+            emit((**right).clone());
+            shrink_helper(left, depth, &mut |left| emit(DeBruijn::App(Box::new(left), right.clone())));
+            shrink_helper(right, depth, &mut |right| emit(DeBruijn::App(left.clone(), Box::new(right))));
+        },
+        _ => {}
+    }
+}
+
+// The body of a binder that does not use it, with the binder taken away
+fn unbind(term : &DeBruijn, cutoff : usize) -> Option<DeBruijn> {
+    match term {
+        DeBruijn::Var(index) if *index == cutoff => None,
+        DeBruijn::Var(index) if *index > cutoff => Some(DeBruijn::Var(index - 1)),
+        DeBruijn::Abs(name, body) => unbind(body, cutoff + 1).map(|body| DeBruijn::Abs(name.clone(), Box::new(body))),
+        DeBruijn::App(left, right) => Some(DeBruijn::App(
+            Box::new(unbind(left, cutoff)?),
+            Box::new(unbind(right, cutoff)?))),
+        term => Some(term.clone())
+    }
+}
+
+// Takes the first smaller term the property still fails on until there is
+// none, the result fails with the message returned
+pub fn minimize<F>(term : DeBruijn, message : String, property : F) -> (DeBruijn, String)
+    where F : Fn(&DeBruijn) -> Result<(), String>
+{
+    let mut term = term;
+    let mut message = message;
+    'outer: loop {
+        for candidate in shrink(&term) {
+            if let Err(failure) = property(&candidate) {
+                term = candidate;
+                message = failure;
+                continue 'outer;
+            }
+        }
+        return (term, message);
+    }
+}
@@ -0,0 +1,106 @@
+// Compares `Layout::to_json` for a few standard terms, before and after
+// normalizing them, against the files in tests/golden. Run with
+// `OLETTE_BLESS=1` to write the files again after an intended change.
+extern crate olette;
+extern crate serde_json;
+
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::hash::{Hash, Hasher};
+use std::fs;
+
+mod common;
+
+use olette::abstract_algorithm::{Layout, Net};
+use olette::limits::{Limits, Outcome};
+use olette::typical::borrow_names;
+
+use common::build;
+
+const TERMS : [(&str, &str); 8] = [
+    ("identity", r"\x.x"),
+    ("free_argument", r"(\x.x) y"),
+    ("k", "K"),
+    ("skk", "S K K"),
+    ("two", "two"),
+    ("plus_two_three", "plus two three"),
+    ("self_application", r"(\x.x x) (\y.y)"),
+    ("shared_argument", r"(\f.f (f z)) (\x.x)")
+];
+
+fn snapshot(source : &str) -> String {
+    let (mut net, names) = build(source);
+    let names = borrow_names(&names);
+    let layout = Layout::new();
+    let before : serde_json::Value = serde_json::from_str(&layout.to_json(&net, &names)).unwrap();
+    let report = net.normalize(&Limits::steps(1_000)).unwrap();
+    assert_eq!(report.outcome, Outcome::Normal, "{}", source);
+    let after : serde_json::Value = serde_json::from_str(&layout.to_json(&net, &names)).unwrap();
+    let result = serde_json::json!({
+        "term": source,
+        "net": before,
+        "normal": after
+    });
+    serde_json::to_string_pretty(&result).unwrap() + "\n"
+}
+
+#[test]
+fn to_json_matches_the_golden_files() {
+    let bless = env::var("OLETTE_BLESS").is_ok();
+    let mut mismatched = vec![];
+    for (name, source) in TERMS.iter() {
+        let path = format!("tests/golden/{}.json", name);
+        let actual = snapshot(source);
+        if bless {
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&path)
+            .unwrap_or_else(|error| panic!("{}: {}, run with OLETTE_BLESS=1 to write it", path, error));
+        if actual != expected {
+            mismatched.push(path);
+        }
+    }
+    assert!(mismatched.is_empty(), "{} differ, run with OLETTE_BLESS=1 if that is intended", mismatched.join(", "));
+}
+
+// Separately built nets print the same, whatever the hashing of the run
+#[test]
+fn debug_output_is_the_same_every_time() {
+    for (_, source) in TERMS.iter() {
+        let (mut first, _) = build(source);
+        let (mut second, _) = build(source);
+        assert_eq!(format!("{:?}", first), format!("{:?}", second));
+        first.normalize(&Limits::steps(1_000)).unwrap();
+        second.normalize(&Limits::steps(1_000)).unwrap();
+        assert_eq!(format!("{:?}", first), format!("{:?}", second));
+    }
+}
+
+fn hash(net : &Net) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    net.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Nets compare by their agents and wires, whatever their history
+#[test]
+fn separately_built_nets_are_equal() {
+    for (_, source) in TERMS.iter() {
+        let (mut first, _) = build(source);
+        let (second, _) = build(source);
+        assert!(first == second, "{}", source);
+        assert_eq!(hash(&first), hash(&second), "{}", source);
+
+        let report = first.normalize(&Limits::steps(1_000)).unwrap();
+        if report.total() > 0 {
+            assert!(first != second, "{}", source);
+            first.undo();
+            assert!(first == second, "{}", source);
+            assert_eq!(hash(&first), hash(&second), "{}", source);
+        }
+    }
+    let (identity, _) = build(r"\x.x");
+    let (other, _) = build(r"\x.\y.x");
+    assert!(identity != other);
+}
@@ -0,0 +1,68 @@
+// Undoing and redoing interactions with `Net::undo` and `Net::redo`
+extern crate olette;
+
+mod common;
+
+use olette::abstract_algorithm::{Net, RuleKind};
+use olette::limits::{Limits, Outcome};
+
+use common::build;
+
+#[test]
+fn steps_and_normalizations_are_undone_and_redone() {
+    // The application is agent 2 and the lambda agent 3
+    let (mut net, _) = Net::from_text("R(r)\n@0(l, r, a)\nL0:x(l, b, b)\nF0:y(a)").unwrap();
+    let start = net.clone();
+    assert!(!net.can_undo() && !net.can_redo());
+
+    assert_eq!(net.reduction_step(2, RuleKind::Auto).unwrap(), RuleKind::Cancel);
+    let stepped = net.clone();
+    assert!(net.can_undo());
+    assert!(net.undo());
+    assert!(net == start);
+    assert!(!net.can_undo() && net.can_redo());
+    assert!(net.redo());
+    assert!(net == stepped);
+    assert!(!net.redo());
+
+    // A whole normalization is a single change
+    let mut net = build("plus two three").0;
+    let start = net.clone();
+    let report = net.normalize(&Limits::steps(1_000)).unwrap();
+    assert!(report.total() > 1);
+    let normal = net.clone();
+    assert!(net.undo());
+    assert!(net == start);
+    assert!(!net.undo());
+    assert!(net.redo());
+    assert!(net == normal);
+
+    // Changing the net after undoing drops what could be redone
+    assert!(net.undo());
+    net.normalize(&Limits::steps(1)).unwrap();
+    assert!(!net.can_redo());
+}
+
+#[test]
+fn only_the_last_changes_are_kept() {
+    // Matches `HISTORY_LIMIT`
+    const LIMIT : usize = 256;
+    let mut net = build("mult 10 10").0;
+    let mut changes = 0;
+    while net.normalize(&Limits::steps(1)).unwrap().outcome != Outcome::Normal {
+        changes += 1;
+    }
+    assert!(changes > LIMIT, "only {} changes", changes);
+
+    let mut undone = 0;
+    while net.undo() {
+        undone += 1;
+    }
+    assert_eq!(undone, LIMIT);
+    let mut redone = 0;
+    while net.redo() {
+        redone += 1;
+    }
+    assert_eq!(redone, LIMIT);
+    assert_eq!(net.normalize(&Limits::steps(1)).unwrap().outcome, Outcome::Normal);
+}
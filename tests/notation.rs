@@ -0,0 +1,101 @@
+// Nets written in the text notation of `Net::from_text` and `Net::to_text`
+extern crate olette;
+
+mod common;
+mod generator;
+
+use std::fs;
+
+use olette::abstract_algorithm::{AgentKind, Net, NotationErrorKind, RuleKind};
+use olette::limits::{Limits, Outcome};
+use olette::typical::{borrow_names, Rules};
+
+use generator::{Rng, Shape};
+
+#[test]
+fn printed_nets_read_back_the_same() {
+    let mut rng = Rng::new(6);
+    for size in 2..40 {
+        let term = generator::term(&mut rng, &Shape::default(), size);
+        let (tree, names) = generator::to_tree(&term);
+        let mut net = Net::from_tree(&tree);
+        let text = net.to_text(&borrow_names(&names));
+
+        let (mut read, read_names) = Net::from_text(&text).unwrap();
+        assert_eq!(read.to_text(&borrow_names(&read_names)), text);
+
+        // Both reduce the same way, having the same wires in the same order
+        let limits = Limits::steps(2_000);
+        let report = net.normalize(&limits).unwrap();
+        let read_report = read.normalize(&limits).unwrap();
+        assert_eq!(report.total(), read_report.total(), "{}", text);
+        if report.outcome == Outcome::Normal {
+            assert_eq!(net.to_tree().unwrap(), read.to_tree().unwrap(), "{}", text);
+        }
+    }
+}
+
+// Names the notation can not take as they are are quoted
+#[test]
+fn symbolic_names_read_back_the_same() {
+    let (_, names) = Net::from_text("R(a)\nL0:\"+\"(a, b, b)").unwrap();
+    assert_eq!(names.get(&1).map(|name| name.as_str()), Some("+"));
+
+    let (tree, names) = common::parse(r#"\+ "x.+ "x y"#);
+    let names = borrow_names(&names);
+    let text = Net::from_tree(&tree).to_text(&names);
+    assert_eq!(text.lines().filter(|line| line.contains(r#":"+"("#) || line.contains(r#":"\"x"("#)).count(), 2, "{}", text);
+
+    let (read, read_names) = Net::from_text(&text).unwrap();
+    assert_eq!(read.to_text(&borrow_names(&read_names)), text);
+    assert_eq!(read.to_tree().unwrap().to_string(&borrow_names(&read_names)), tree.to_string(&names));
+
+    assert_eq!(Net::from_text("R(a) F0:\"+(a)").unwrap_err().kind, NotationErrorKind::UnclosedQuote);
+}
+
+#[test]
+fn hand_written_nets_reduce() {
+    let text = fs::read_to_string("tests/fixtures/shared_identity.net").unwrap();
+    let (mut net, names) = Net::from_text(&text).unwrap();
+    let report = net.normalize(&Limits::steps(100)).unwrap();
+    assert_eq!(report.outcome, Outcome::Normal);
+    assert_eq!(net.to_tree().unwrap().to_string(&borrow_names(&names)), "λx.x");
+    assert_eq!(net.to_text(&borrow_names(&names)), "R(a)\nL0:x(a, b, b)\n");
+}
+
+// The variable of an eta redex reaches the argument through a bracket and a
+// croissant one level up. A croissant at level 0 on its own is not such a
+// pair and must not be taken for the first half of one.
+#[test]
+fn eta_redexes_are_recognized_by_their_controls() {
+    let (mut net, names) = Net::from_text("R(r)\nL0:x(r, b, v)\n@0(f, b, a)\nF0:f(f)\nB0(v, w)\nC1(w, a)").unwrap();
+    let report = net.normalize_with(Rules::BetaEta, &Limits::steps(10)).unwrap();
+    assert_eq!(report.outcome, Outcome::Normal);
+    assert_eq!(report.total(), 1);
+    assert_eq!(net.to_text(&borrow_names(&names)), "R(a)\nF0:f(a)\n");
+
+    let text = "R(r)\nL0:x(r, b, v)\n@0(f, b, a)\nF0:f(f)\nC0(v, a)\n";
+    let (mut net, _) = Net::from_text(text).unwrap();
+    let lambda = 2;
+    assert_eq!(net.reduction_step(lambda, RuleKind::Eta).unwrap(), RuleKind::None);
+    let report = net.normalize_with(Rules::BetaEta, &Limits::steps(10)).unwrap();
+    assert_eq!(report.total(), 0);
+    assert!(net.validate().is_empty());
+}
+
+#[test]
+fn malformed_nets_are_refused() {
+    let error = |text : &str| Net::from_text(text).unwrap_err();
+
+    assert_eq!(error("R(a)\nL0(a, b)").kind, NotationErrorKind::PortCount(AgentKind::Lambda, 2));
+    assert_eq!(error("R(a) X(a)").kind, NotationErrorKind::UnknownAgent('X'));
+    assert_eq!(error("R(a) e0(a").kind, NotationErrorKind::MissingParen);
+    assert_eq!(error("R(a) e0()").kind, NotationErrorKind::MissingWire);
+    assert_eq!(error("R(a) D0:x(a, b, b)").kind, NotationErrorKind::MisplacedName(AgentKind::Duplicator));
+    assert_eq!(error("R(a)\nL0:x(a, b, c)").kind, NotationErrorKind::WireCount("b".to_string(), 1));
+    assert_eq!(error("L0:x(a, b, b) e0(a)").kind, NotationErrorKind::RootCount(0));
+
+    let located = error("R(a) # the root\n\tL0(a, b)");
+    assert_eq!((located.line, located.column), (2, 2));
+    assert_eq!(located.to_string(), "error: Lambda agents have 3 ports, not 2 at 2:2\n\tL0(a, b)\n\t^");
+}
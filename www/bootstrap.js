@@ -20,6 +20,7 @@ const dropdown_button = document.getElementById("dropdown_button");
 const auto_choice = document.getElementById("auto");
 const duplicate_choice = document.getElementById("duplicate");
 const cancel_choice = document.getElementById("cancel");
+const eta_choice = document.getElementById("eta");
 const time_input = document.getElementById("time_input");
 const timer_set_button = document.getElementById("timer_set_button");
 const title_input = document.getElementById("title_input");
@@ -31,45 +32,6 @@ var time_delay = 1500;
 var rotationAngle = 0;
 
 
-class Node {
-    constructor(value, next, prev) {
-        this.value = value;
-        this.next = next;
-        this.prev = prev;
-    }
-}
-
-class LinkedList {
-    constructor() {
-        this.head = null;
-        this.tail = null;
-        this.cur = null;
-        this.size = 0;
-    }
-
-    add(value) {
-        const node = new Node(value, null, this.tail);
-        this.size++;
-        if (this.head == null) {
-            this.head = node;
-            this.tail = node;
-        }
-        else {
-            this.tail.next = node;
-        }
-        this.tail = node;
-        this.cur = this.tail;
-    }
-    addHead(value) {
-        const node = new Node(value, null, null);
-        this.head = node;
-        this.size = 1;
-        this.cur = this.head;
-        this.tail = this.head;
-    }
-}
-var history = new LinkedList();
-
 Promise.all([promise]).then(promises => {
     var olette = promises[0];
 
@@ -119,6 +81,11 @@ Promise.all([promise]).then(promises => {
         rule_kind = "cancel";
     });
 
+    eta_choice.addEventListener("click", event => {
+        event.stopPropagation();
+        rule_kind = "eta";
+    });
+
 
     button.addEventListener('click', button_interact(button, load), true);
 
@@ -404,58 +371,117 @@ Promise.all([promise]).then(promises => {
         wire.color = "black";
     }
 
+    // Spans are byte offsets into the UTF-8 input, so convert them before selecting
+    function showError(error) {
+        let bytes = new TextEncoder().encode(input.value);
+        let decoder = new TextDecoder();
+        let start = decoder.decode(bytes.slice(0, error.start)).length;
+        let end = decoder.decode(bytes.slice(0, error.end)).length;
+        input.focus();
+        input.setSelectionRange(start, Math.max(end, start + 1));
+        alert(error.text);
+    }
+
+    // Failed calls into the module throw the JSON text of {"error": {...}}
+    function errorOf(thrown) {
+        try {
+            return JSON.parse(thrown).error;
+        } catch (e) {
+            return { "message": String(thrown) };
+        }
+    }
+
     function load() {
+        let result;
+        try {
+            result = JSON.parse(olette.load_net(input.value));
+        } catch (thrown) {
+            let error = errorOf(thrown);
+            if (error.start !== undefined) {
+                showError(error);
+            } else {
+                alert(error.message);
+            }
+            return;
+        }
         clear();
-        data = JSON.parse(olette.load_net(input.value));
+        data = result;
         reduce_auto_button.removeAttribute("disabled");
-        back_button.setAttribute("disabled", "");
         continue_reduce = true;
-        history.addHead(JSON.stringify(data));
+        refresh_history();
         update(1.0);
         Storage.set("net", data);
         updateAngle(0);
     }
 
+    // Hands where the agents are drawn to the module, so that the net it
+    // gives back after a change keeps them there
+    function sync_layout() {
+        let darray = { "nodes": [] };
+        for (let i = 0; i < data.nodes.length; ++i) {
+            let d = data.nodes[i];
+            let k = {
+                "id": d.id,
+                "x": d.x,
+                "y": d.y,
+                "fixed": d.fixed,
+                "label": d.label,
+                "title": d.title,
+                "rotation": d.rotation
+            };
+            darray.nodes.push(k);
+        }
+        olette.update_net(JSON.stringify(darray));
+    }
+
+    // Shows the net the module gave back after a reduction, undo or redo
+    function show(patch, alpha) {
+        simulation.stop();
+        data = patch;
+        for (let i = 0; i < data.nodes.length; ++i) {
+            let d = data.nodes[i];
+            if (d.fixed) {
+                d.fx = d.x;
+                d.fy = d.y;
+            }
+        }
+        update(alpha);
+        Storage.set("net", data);
+        selection = undefined;
+        reduce_button.setAttribute("disabled", "");
+        refresh_history();
+    }
+
+    // The history of the net is kept by the module, the buttons follow it
+    function refresh_history() {
+        if (olette.can_undo()) {
+            back_button.removeAttribute("disabled");
+        } else {
+            back_button.setAttribute("disabled", "");
+        }
+        if (olette.can_redo()) {
+            forward_button.removeAttribute("disabled");
+        } else {
+            forward_button.setAttribute("disabled", "");
+        }
+    }
+
     function reduce() {
         if (!reduce_button.hasAttribute("disabled")) {
-            let darray = { "nodes": [] };
-            for (let i = 0; i < data.nodes.length; ++i) {
-                let d = data.nodes[i];
-                let k = {
-                    "id": d.id,
-                    "x": d.x,
-                    "y": d.y,
-                    "fixed": d.fixed,
-                    "label": d.label,
-                    "title": d.title,
-                    "rotation": d.rotation
-                };
-                darray.nodes.push(k);
+            var patch;
+            try {
+                sync_layout();
+                patch = JSON.parse(olette.reduce_net(selection, rule_kind));
+            } catch (thrown) {
+                alert(errorOf(thrown).message);
+                return;
             }
-            olette.update_net(JSON.stringify(darray));
-            var patch = JSON.parse(olette.reduce_net(selection, rule_kind));
-            simulation.stop();
-            data = patch;
-            for (let i = 0; i < data.nodes.length; ++i) {
-                let d = data.nodes[i];
-                if (d.fixed) {
-                    d.fx = d.x;
-                    d.fy = d.y;
-                }
-            }
-            history.tail = history.cur;
-            history.add(JSON.stringify(data));
-            history.cur = history.tail;
-            update(0.6);
-            Storage.set("net", data);
-            selection = undefined;
-            reduce_button.setAttribute("disabled", "");
-            back_button.removeAttribute("disabled");
+            show(patch, 0.6);
         }
     }
 
     function reduce_auto() {
-        if (history.cur != null) {
+        if (data !== undefined) {
             for (let i = 0; i < data.nodes.length; ++i) {
                 reduce_auto_button.setAttribute("disabled", "");
                 if (continue_reduce == false) {
@@ -503,14 +529,6 @@ Promise.all([promise]).then(promises => {
         if (cur != null) {
             cur.__data__.title = title_input.value;
             update(1.0);
-            let cur_data = JSON.parse(history.cur.value);
-            for (let i = 0; i < cur_data.nodes.length; ++i) {
-                if (cur.__data__.id == cur_data.nodes[i].id) {
-                    cur_data.nodes[i].title = cur.__data__.title;
-                }
-            }
-            let new_cur = JSON.stringify(cur_data);
-            history.cur.value = new_cur;
         }
         title_input.value ='';
     }
@@ -525,84 +543,39 @@ Promise.all([promise]).then(promises => {
         if (cur != null) {
             cur.__data__.rotation = nAngle;
             update(1.0);
-            let cur_data = JSON.parse(history.cur.value);
-            for (let i = 0; i < cur_data.nodes.length; ++i) {
-                if (cur.__data__.id == cur_data.nodes[i].id) {
-                    cur_data.nodes[i].rotation = cur.__data__.rotation;
-                }
-            }
-            let new_cur = JSON.stringify(cur_data);
-            history.cur.value = new_cur;
         }
         rotationAngle = nAngle;
     }
 
+    // Takes back the last reduction, a misclick costs nothing
     function back() {
         if (!back_button.hasAttribute("disabled")) {
             continue_reduce = false;
             reduce_auto_button.removeAttribute("disabled");
-            if (history.cur.prev != null) {
-                history.cur = history.cur.prev;
+            var patch;
+            try {
+                sync_layout();
+                patch = JSON.parse(olette.undo_net());
+            } catch (thrown) {
+                alert(errorOf(thrown).message);
+                return;
             }
-            let previous_data = history.cur.value;
-            olette.rebuild_net(previous_data);
-            data = JSON.parse(previous_data);
-            selection = undefined;
-            for (let i = 0; i < data.nodes.length; ++i) {
-                data.nodes[i].color = "black";
-            }
-            for (let i = 0; i < data.links.length; ++i) {
-                data.links[i].color = "#708090";
-
-            }
-
-            for (let i = 0; i < data.nodes.length; ++i) {
-                let d = data.nodes[i];
-                if (d.fixed) {
-                    d.fx = d.x;
-                    d.fy = d.y;
-                }
-            }
-            update(1);
+            show(patch, 1);
             continue_reduce = true;
-            if (history.cur.prev == null) {
-                back_button.setAttribute("disabled", "");
-            }
-            forward_button.removeAttribute("disabled");
         }
-
     }
 
     function forward() {
         if (!forward_button.hasAttribute("disabled")) {
-            if (history.cur.next != null) {
-                history.cur = history.cur.next;
-            }
-            let previous_data = history.cur.value;
-            olette.rebuild_net(previous_data);
-            data = JSON.parse(previous_data);
-            selection = undefined;
-            for (let i = 0; i < data.nodes.length; ++i) {
-                data.nodes[i].color = "black";
-            }
-            for (let i = 0; i < data.links.length; ++i) {
-                data.links[i].color = "#708090";
-
-            }
-
-            for (let i = 0; i < data.nodes.length; ++i) {
-                let d = data.nodes[i];
-                if (d.fixed) {
-                    d.fx = d.x;
-                    d.fy = d.y;
-                }
+            var patch;
+            try {
+                sync_layout();
+                patch = JSON.parse(olette.redo_net());
+            } catch (thrown) {
+                alert(errorOf(thrown).message);
+                return;
             }
-            update(1);
-            continue_reduce = true;
-            if (history.cur.next == null) {
-                forward_button.setAttribute("disabled", "");
-            }
-            back_button.removeAttribute("disabled");
+            show(patch, 1);
         }
     }
 
@@ -643,6 +616,9 @@ Promise.all([promise]).then(promises => {
         } else if (key == 67 && selection != undefined && alt == true) {//c + alt
             cancel_choice.click();
             reduce_button.click();
+        } else if (key == 69 && selection != undefined && alt == true) {//e + alt
+            eta_choice.click();
+            reduce_button.click();
         } else if (key == 82 && alt == true) { //r + alt
             reduce_auto_button.click();
         } else if (key == 37 ) { //left arrow
@@ -1,6 +1,14 @@
 import * as wasm from "olette";
 
-export function load_net(term) { return wasm.load_net(term); }
-export function reduce_net(index, kind) { return wasm.reduce(index, kind); }
-export function update_net(json) { return wasm.update(json); }
-export function rebuild_net(json) { return wasm.rebuild(json);}
+// The net on screen, other views can open their own with `new_session`
+const session = new wasm.Session();
+
+export function new_session() { return new wasm.Session(); }
+export function load_net(term) { return session.load(term); }
+export function reduce_net(index, kind) { return session.reduce(index, kind); }
+export function update_net(json) { return session.update(json); }
+export function rebuild_net(json) { return session.rebuild(json);}
+export function undo_net() { return session.undo(); }
+export function redo_net() { return session.redo(); }
+export function can_undo() { return session.can_undo(); }
+export function can_redo() { return session.can_redo(); }